@@ -0,0 +1,99 @@
+use std::fmt;
+
+use crate::point::Point2D;
+
+/// A puzzle answer.  Displays in the form the puzzle site accepts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Integer(i64),
+    Text(String),
+    /// Comma separated values with no spaces, e.g. `x,y` or `x,y,size`.
+    Coordinate(Vec<i64>),
+    /// Letters drawn on a grid that must be read by a person.  Displays as
+    /// the lines of the drawing.
+    Art(String),
+}
+
+impl Answer {
+    pub fn coordinate<T: TryInto<i64>>(values: impl IntoIterator<Item = T>) -> Answer {
+        Answer::Coordinate(values.into_iter().map(to_i64).collect())
+    }
+}
+
+fn to_i64<T: TryInto<i64>>(value: T) -> i64 {
+    value
+        .try_into()
+        .unwrap_or_else(|_| panic!("answer does not fit in an i64"))
+}
+
+macro_rules! impl_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(value: $t) -> Answer {
+                    Answer::Integer(to_i64(value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(u16, i32, u32, i64, u64, usize);
+
+impl From<String> for Answer {
+    fn from(value: String) -> Answer {
+        Answer::Text(value)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Answer {
+        Answer::Text(value.to_string())
+    }
+}
+
+impl<T: TryInto<i64>> From<Point2D<T>> for Answer {
+    fn from(point: Point2D<T>) -> Answer {
+        Answer::coordinate([point.x, point.y])
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Coordinate(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                Ok(())
+            }
+            Answer::Art(s) => write!(f, "{}", s.trim_end()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Answer::from(-42).to_string(), "-42");
+        assert_eq!(Answer::from(2996043280_u64).to_string(), "2996043280");
+        assert_eq!(Answer::from("0124515891").to_string(), "0124515891");
+        assert_eq!(
+            Answer::from(Point2D::<usize>::new(115, 138)).to_string(),
+            "115,138"
+        );
+        assert_eq!(Answer::coordinate([229, 192, 11]).to_string(), "229,192,11");
+        assert_eq!(
+            Answer::Art("X.X\n.X.\n".to_string()).to_string(),
+            "X.X\n.X."
+        );
+    }
+}
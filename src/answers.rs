@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::parse::{ParseError, parse_number};
+use crate::solver::Part;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key {
+    pub input: String,
+    pub day: u8,
+    pub part: Part,
+}
+
+/// Known answers keyed by input name, day and part.
+///
+/// The text format has one answer per line:
+///
+/// ```text
+/// # input day part answer
+/// builtin 1 1 543
+/// builtin 10 1 |
+/// | X....X..XXXXX
+/// | X....X..X....
+/// ```
+///
+/// An answer of `|` starts a multi-line answer made of the following lines
+/// that begin with `| `.  Blank lines and lines starting with `#` are
+/// ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    answers: BTreeMap<Key, String>,
+}
+
+impl Answers {
+    pub fn parse(text: &str) -> Result<Answers, ParseError> {
+        let mut answers = BTreeMap::new();
+        let mut lines = text.lines().enumerate().peekable();
+        while let Some((i, line)) = lines.next() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.splitn(4, ' ');
+            let (Some(input), Some(day), Some(part), Some(answer)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(ParseError::line(i, line, "expected: input day part answer"));
+            };
+            let day = parse_number(i, line, day)?;
+            let part = Part::from_number(parse_number(i, line, part)?)
+                .ok_or_else(|| ParseError::new(i, line, part, "expected part 1 or 2"))?;
+            let answer = if answer == "|" {
+                let mut art = Vec::new();
+                while let Some((_, rest)) = lines.next_if(|(_, line)| line.starts_with('|')) {
+                    art.push(rest.strip_prefix("| ").unwrap_or(""));
+                }
+                art.join("\n")
+            } else {
+                answer.to_string()
+            };
+            let key = Key {
+                input: input.to_string(),
+                day,
+                part,
+            };
+            if answers.insert(key, answer).is_some() {
+                return Err(ParseError::line(i, line, "duplicate answer"));
+            }
+        }
+        Ok(Answers { answers })
+    }
+
+    pub fn get(&self, input: &str, day: u8, part: Part) -> Option<&str> {
+        let key = Key {
+            input: input.to_string(),
+            day,
+            part,
+        };
+        self.answers.get(&key).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key: Key, answer: String) -> Option<String> {
+        self.answers.insert(key, answer)
+    }
+
+    /// Iterates over the answers in input, day and part order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &str)> {
+        self.answers
+            .iter()
+            .map(|(key, answer)| (key, answer.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# input day part answer")?;
+        for (key, answer) in self.iter() {
+            write!(f, "{} {} {} ", key.input, key.day, key.part)?;
+            if answer.contains('\n') {
+                writeln!(f, "|")?;
+                for line in answer.lines() {
+                    writeln!(f, "| {}", line)?;
+                }
+            } else {
+                writeln!(f, "{}", answer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inputs;
+    use crate::registry;
+    use crate::solver::PARTS;
+
+    const EXAMPLE: &str = "\
+# input day part answer
+builtin 1 1 543
+
+builtin 13 2 0,98
+builtin 10 1 |
+| X..X
+| .XX.
+builtin 10 2 10521
+";
+
+    #[test]
+    fn test_parse() {
+        let answers = Answers::parse(EXAMPLE).unwrap();
+        assert_eq!(answers.len(), 4);
+        assert_eq!(answers.get("builtin", 1, Part::One), Some("543"));
+        assert_eq!(answers.get("builtin", 13, Part::Two), Some("0,98"));
+        assert_eq!(answers.get("builtin", 10, Part::One), Some("X..X\n.XX."));
+        assert_eq!(answers.get("builtin", 10, Part::Two), Some("10521"));
+        assert_eq!(answers.get("other", 1, Part::One), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let answers = Answers::parse(EXAMPLE).unwrap();
+        assert_eq!(Answers::parse(&answers.to_string()).unwrap(), answers);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = Answers::parse("builtin 1 3 543\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 11));
+        let err = Answers::parse("builtin 1 1 543\nbuiltin 1 1 544\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(Answers::parse("builtin 1\n").is_err());
+    }
+
+    #[test]
+    fn test_checked_in_answers_cover_builtin_inputs() {
+        let answers = Answers::parse(include_str!("../answers.txt")).unwrap();
+        for day in registry::DAYS {
+            if inputs::builtin(day.number).is_none() {
+                continue;
+            }
+            for part in PARTS {
+                assert!(
+                    answers.get(inputs::BUILTIN, day.number, part).is_some(),
+                    "no answer for day {} part {}",
+                    day.number,
+                    part
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::registry::Day;
+use crate::solver::{Error, PARTS, Part};
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// A global allocator that counts allocations, so benchmarks can report
+/// them.  Install it in a binary with `#[global_allocator]`; otherwise
+/// every count is zero.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+/// Returns the number of allocations made through [`CountingAllocator`] so
+/// far, by all threads.
+pub fn allocations() -> u64 {
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    Parse,
+    Solve(Part),
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Phase::Parse => f.pad("parse"),
+            Phase::Solve(part) => f.pad(&format!("part{}", part)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    /// Allocations per run, averaged over the runs.
+    pub allocations: u64,
+}
+
+impl Stats {
+    /// Summarizes the durations and total allocations of `runs`.  Panics if
+    /// `durations` is empty.
+    fn new(mut durations: Vec<Duration>, allocations: u64) -> Stats {
+        durations.sort();
+        let runs = durations.len();
+        Stats {
+            runs,
+            min: durations[0],
+            median: durations[runs / 2],
+            max: durations[runs - 1],
+            allocations: allocations / runs as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub day: u8,
+    pub phase: Phase,
+    pub stats: Stats,
+}
+
+// Times `runs` calls of `f`.  Its result goes through `black_box` so the
+// work that computes it is not optimized away, and is dropped after the
+// clock stops.
+fn measure<T>(runs: usize, mut f: impl FnMut() -> T) -> Stats {
+    assert!(runs > 0);
+    let mut durations = Vec::with_capacity(runs);
+    let allocations_before = allocations();
+    for _ in 0..runs {
+        let start = Instant::now();
+        let result = black_box(f());
+        durations.push(start.elapsed());
+        drop(result);
+    }
+    Stats::new(durations, allocations() - allocations_before)
+}
+
+/// Times parsing `input` and solving each part `runs` times.  The parts are
+/// solved from a single parse so their times exclude parsing, and each is
+/// solved once first so a part with no answer fails before it is timed.
+pub fn bench_day(day: &Day, input: &str, runs: usize) -> Result<Vec<Measurement>, Error> {
+    let parsed = day.solver.parse_input(input)?;
+    let mut measurements = vec![Measurement {
+        day: day.number,
+        phase: Phase::Parse,
+        stats: measure(runs, || day.solver.parse_input(black_box(input)).unwrap()),
+    }];
+    for part in PARTS {
+        day.solver.solve_part(part, parsed.as_ref())?;
+        measurements.push(Measurement {
+            day: day.number,
+            phase: Phase::Solve(part),
+            stats: measure(runs, || {
+                day.solver.solve_part(part, black_box(parsed.as_ref()))
+            }),
+        });
+    }
+    Ok(measurements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{inputs, registry};
+
+    #[test]
+    fn test_stats() {
+        let ms = Duration::from_millis;
+        let stats = Stats::new(vec![ms(5), ms(1), ms(9), ms(3)], 8);
+        assert_eq!(stats.runs, 4);
+        assert_eq!(stats.min, ms(1));
+        assert_eq!(stats.median, ms(5));
+        assert_eq!(stats.max, ms(9));
+        assert_eq!(stats.allocations, 2);
+    }
+
+    #[test]
+    fn test_bench_day() {
+        let day = registry::day(8).unwrap();
+        let measurements = bench_day(day, inputs::builtin(8).unwrap(), 3).unwrap();
+        let phases: Vec<Phase> = measurements.iter().map(|m| m.phase).collect();
+        assert_eq!(
+            phases,
+            vec![
+                Phase::Parse,
+                Phase::Solve(Part::One),
+                Phase::Solve(Part::Two)
+            ]
+        );
+        assert!(measurements.iter().all(|m| m.stats.runs == 3));
+        assert!(bench_day(day, "1 1", 1).is_err());
+    }
+}
@@ -0,0 +1,425 @@
+use std::io::Read;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+
+use advent_of_code_2018::answers::Answers;
+use advent_of_code_2018::bench::{self, CountingAllocator};
+use advent_of_code_2018::inputs::{self, InputStore};
+use advent_of_code_2018::registry;
+use advent_of_code_2018::solver::Part;
+use advent_of_code_2018::trace::{self, Level};
+use advent_of_code_2018::verify::{self, Outcome};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const USAGE: &str = "\
+Usage: aoc2018 run --day N --part P [--input FILE | --set NAME] [-v|-vv|-vvv]
+       aoc2018 run --all [--set NAME] [--answers FILE] [--jobs N | --parallel]
+       aoc2018 list
+       aoc2018 inputs
+       aoc2018 verify [--answers FILE]
+       aoc2018 bench [--day N] [--runs N] [--set NAME] [--tsv]
+
+All but `list` also take --inputs DIR, the directory of named input sets
+(default inputs).  The set NAME is read from DIR/NAME/dayNN.txt, except
+`builtin`, which is the checked in inputs.  `inputs` lists the sets and the
+days each has.
+
+`run` reads the puzzle input from FILE or the set NAME, or from stdin when
+neither is given or FILE is `-`.  With -v it prints the solver's info
+events to stderr, -vv adds debug events and -vvv trace events.
+
+`run --all` solves every day and part that the set NAME (default builtin)
+has an input for using N threads, or one per CPU with --parallel, and prints a summary.  It
+compares the answers with FILE (default answers.txt, if present) and fails
+if any solver fails or disagrees with it.
+
+`verify` checks every answer in FILE (default answers.txt) against the
+solvers.  `bench` times parsing and each part of one or every day on the
+set NAME (default builtin), printing tab separated values with --tsv.";
+
+const DEFAULT_INPUTS: &str = "inputs";
+const DEFAULT_ANSWERS: &str = "answers.txt";
+
+struct RunArgs {
+    day: Option<u8>,
+    part: Option<Part>,
+    all: bool,
+    input: Option<String>,
+    set: Option<String>,
+    inputs: String,
+    answers: Option<String>,
+    jobs: usize,
+    verbosity: Option<Level>,
+}
+
+fn verbosity(count: usize) -> Option<Level> {
+    match count {
+        0 => None,
+        1 => Some(Level::Info),
+        2 => Some(Level::Debug),
+        _ => Some(Level::Trace),
+    }
+}
+
+fn parse_run_args(args: &[String]) -> Result<RunArgs> {
+    let mut day = None;
+    let mut part = None;
+    let mut input = None;
+    let mut set = None;
+    let mut all = false;
+    let mut inputs = DEFAULT_INPUTS.to_string();
+    let mut answers = None;
+    let mut jobs = 1;
+    let mut verbose = 0;
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| {
+            it.next()
+                .with_context(|| format!("missing value for {}", name))
+        };
+        match arg.as_str() {
+            "--day" => day = Some(value("--day")?.parse().context("invalid --day")?),
+            "--part" => part = Some(value("--part")?.parse().context("invalid --part")?),
+            "--input" => input = Some(value("--input")?.clone()),
+            "--set" => set = Some(value("--set")?.clone()),
+            "--inputs" => inputs = value("--inputs")?.clone(),
+            "--all" => all = true,
+            "--answers" => answers = Some(value("--answers")?.clone()),
+            "--jobs" => jobs = value("--jobs")?.parse().context("invalid --jobs")?,
+            "--parallel" => {
+                jobs = std::thread::available_parallelism().map_or(1, |n| n.get());
+            }
+            "-v" | "-vv" | "-vvv" => verbose += arg.len() - 1,
+            other => bail!("unexpected argument: {}", other),
+        }
+    }
+    if all {
+        if day.is_some() || part.is_some() || input.is_some() {
+            bail!("--all cannot be used with --day, --part or --input");
+        }
+    } else {
+        if day.is_none() {
+            bail!("--day is required");
+        }
+        if part.is_none() {
+            bail!("--part is required");
+        }
+        if answers.is_some() || jobs != 1 {
+            bail!("--answers, --jobs and --parallel require --all");
+        }
+    }
+    let part = part
+        .map(|part| {
+            Part::from_number(part).with_context(|| format!("--part must be 1 or 2, got {}", part))
+        })
+        .transpose()?;
+    if input.is_some() && set.is_some() {
+        bail!("--input and --set cannot be used together");
+    }
+    if jobs == 0 {
+        bail!("--jobs must be at least 1");
+    }
+    Ok(RunArgs {
+        day,
+        part,
+        all,
+        input,
+        set,
+        inputs,
+        answers,
+        jobs,
+        verbosity: verbosity(verbose),
+    })
+}
+
+fn read_input(path: Option<&str>) -> Result<String> {
+    match path {
+        None | Some("-") => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .context("reading stdin")?;
+            Ok(input)
+        }
+        Some(path) => std::fs::read_to_string(path).with_context(|| format!("reading {}", path)),
+    }
+}
+
+fn run(args: &[String]) -> Result<()> {
+    let args = parse_run_args(args)?;
+    if args.all {
+        return run_all(&args);
+    }
+    let (Some(number), Some(part)) = (args.day, args.part) else {
+        unreachable!("checked by parse_run_args");
+    };
+    let day = registry::day(number).with_context(|| format!("no solver for day {}", number))?;
+    let input = match &args.set {
+        Some(set) => InputStore::new(&args.inputs).load(set, day.number)?,
+        None => read_input(args.input.as_deref())?,
+    };
+    let solve = || day.solver.solve(part, &input);
+    let answer = match args.verbosity {
+        Some(max_level) => trace::with_observer(Rc::new(trace::Stderr { max_level }), solve),
+        None => solve(),
+    }
+    .with_context(|| format!("solving day {} part {}", day.number, part))?;
+    println!("{}", answer);
+    Ok(())
+}
+
+fn run_all(args: &RunArgs) -> Result<()> {
+    let answers = match &args.answers {
+        Some(path) => read_answers(path)?,
+        None if Path::new(DEFAULT_ANSWERS).exists() => read_answers(DEFAULT_ANSWERS)?,
+        None => Answers::default(),
+    };
+    let set = args.set.as_deref().unwrap_or(inputs::BUILTIN);
+    let store = InputStore::new(&args.inputs);
+    let available = store.days(set);
+    let days: Vec<u8> = registry::DAYS
+        .iter()
+        .map(|day| day.number)
+        .filter(|day| available.contains(day))
+        .collect();
+
+    let start = Instant::now();
+    let results = verify::run_all(
+        &days,
+        set,
+        &answers,
+        |name, day| store.load(name, day),
+        args.jobs,
+    );
+    let elapsed = start.elapsed();
+
+    println!("{:>3} {:>4} {:>12}  result", "day", "part", "time");
+    let (mut passed, mut unchecked, mut failures) = (0, 0, 0);
+    for v in results.iter() {
+        print!(
+            "{:>3} {:>4} {:>12.3?}  ",
+            v.day,
+            v.part.number(),
+            v.duration
+        );
+        match &v.outcome {
+            Outcome::Pass => passed += 1,
+            Outcome::Unchecked(_) => unchecked += 1,
+            _ => failures += 1,
+        }
+        print_outcome(&v.outcome, answers.get(set, v.day, v.part));
+    }
+    println!(
+        "{} parts in {:.3?}: {} passed, {} without an answer, {} failed",
+        results.len(),
+        elapsed,
+        passed,
+        unchecked,
+        failures
+    );
+    if failures > 0 {
+        bail!("{} of {} parts failed", failures, results.len());
+    }
+    Ok(())
+}
+
+fn read_answers(path: &str) -> Result<Answers> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+    Answers::parse(&text).with_context(|| format!("parsing {}", path))
+}
+
+// Prints the result column of the run and verify tables.  A passing
+// outcome shows `answer`, if given.
+fn print_outcome(outcome: &Outcome, answer: Option<&str>) {
+    let labelled = |label: &str, text: &str| {
+        if text.contains('\n') {
+            println!("{}\n{}", label, text);
+        } else {
+            println!("{} {}", label, text);
+        }
+    };
+    match outcome {
+        Outcome::Pass => match answer {
+            Some(answer) => labelled("pass", answer),
+            None => println!("pass"),
+        },
+        Outcome::Unchecked(actual) => labelled("solved", actual),
+        Outcome::Mismatch { expected, actual } => {
+            if expected.contains('\n') || actual.contains('\n') {
+                println!("MISMATCH\nexpected:\n{}\nactual:\n{}", expected, actual);
+            } else {
+                println!("MISMATCH expected {} got {}", expected, actual);
+            }
+        }
+        Outcome::Fail(e) => println!("FAIL {}", e),
+    }
+}
+
+fn verify(args: &[String]) -> Result<()> {
+    let mut path = DEFAULT_ANSWERS.to_string();
+    let mut inputs = DEFAULT_INPUTS.to_string();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| {
+            it.next()
+                .with_context(|| format!("missing value for {}", name))
+        };
+        match arg.as_str() {
+            "--answers" => path = value("--answers")?.clone(),
+            "--inputs" => inputs = value("--inputs")?.clone(),
+            other => bail!("unexpected argument: {}", other),
+        }
+    }
+    let answers = read_answers(&path)?;
+
+    let store = InputStore::new(inputs);
+    let verifications = verify::verify(&answers, |name, day| store.load(name, day));
+    let mut failures = 0;
+    println!(
+        "{:<12} {:>3} {:>4} {:>12}  result",
+        "input", "day", "part", "time"
+    );
+    for v in verifications.iter() {
+        print!(
+            "{:<12} {:>3} {:>4} {:>12.3?}  ",
+            v.input,
+            v.day,
+            v.part.number(),
+            v.duration
+        );
+        if v.outcome.is_failure() {
+            failures += 1;
+        }
+        print_outcome(&v.outcome, None);
+    }
+    if failures > 0 {
+        bail!(
+            "{} of {} answers did not verify",
+            failures,
+            verifications.len()
+        );
+    }
+    println!("all {} answers verified", verifications.len());
+    Ok(())
+}
+
+fn bench(args: &[String]) -> Result<()> {
+    let mut day = None;
+    let mut runs = 10;
+    let mut tsv = false;
+    let mut set = inputs::BUILTIN.to_string();
+    let mut inputs = DEFAULT_INPUTS.to_string();
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| {
+            it.next()
+                .with_context(|| format!("missing value for {}", name))
+        };
+        match arg.as_str() {
+            "--day" => day = Some(value("--day")?.parse().context("invalid --day")?),
+            "--runs" => runs = value("--runs")?.parse().context("invalid --runs")?,
+            "--set" => set = value("--set")?.clone(),
+            "--inputs" => inputs = value("--inputs")?.clone(),
+            "--tsv" => tsv = true,
+            other => bail!("unexpected argument: {}", other),
+        }
+    }
+    if runs == 0 {
+        bail!("--runs must be at least 1");
+    }
+    let store = InputStore::new(inputs);
+    let days: Vec<_> = match day {
+        Some(number) => {
+            vec![registry::day(number).with_context(|| format!("no solver for day {}", number))?]
+        }
+        None => {
+            let available = store.days(&set);
+            registry::DAYS
+                .iter()
+                .filter(|day| available.contains(&day.number))
+                .collect()
+        }
+    };
+
+    if tsv {
+        println!("day\tphase\truns\tmin_ns\tmedian_ns\tmax_ns\tallocations");
+    } else {
+        println!(
+            "{:>3} {:<6} {:>12} {:>12} {:>12} {:>12}",
+            "day", "phase", "min", "median", "max", "allocations"
+        );
+    }
+    for day in days {
+        let input = store.load(&set, day.number)?;
+        let measurements = bench::bench_day(day, &input, runs)
+            .with_context(|| format!("benchmarking day {}", day.number))?;
+        for m in measurements {
+            let s = m.stats;
+            if tsv {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    m.day,
+                    m.phase,
+                    s.runs,
+                    s.min.as_nanos(),
+                    s.median.as_nanos(),
+                    s.max.as_nanos(),
+                    s.allocations
+                );
+            } else {
+                println!(
+                    "{:>3} {:<6} {:>12.3?} {:>12.3?} {:>12.3?} {:>12}",
+                    m.day, m.phase, s.min, s.median, s.max, s.allocations
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn list() {
+    for day in registry::DAYS {
+        println!("{:2} {}", day.number, day.title);
+    }
+}
+
+fn list_inputs(args: &[String]) -> Result<()> {
+    let dir = match args {
+        [] => DEFAULT_INPUTS,
+        [flag, dir] if flag == "--inputs" => dir,
+        _ => bail!("{}", USAGE),
+    };
+    let store = InputStore::new(dir);
+    let names = store
+        .names()
+        .with_context(|| format!("listing input sets in {}", dir))?;
+    for name in names {
+        let days: Vec<String> = store.days(&name).iter().map(u8::to_string).collect();
+        println!("{:<12} {}", name, days.join(" "));
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("run") => run(&args[1..]),
+        Some("verify") => verify(&args[1..]),
+        Some("bench") => bench(&args[1..]),
+        Some("inputs") => list_inputs(&args[1..]),
+        Some("list") => {
+            list();
+            Ok(())
+        }
+        Some("-h" | "--help" | "help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => bail!("{}", USAGE),
+    }
+}
@@ -1,19 +1,33 @@
-#![cfg(test)]
-
 use std::collections::HashSet;
 
-fn lines_of_i32_iterator<'a>(input: &'a str) -> impl Iterator<Item = i32> + 'a + Clone {
-    input.lines().map(|e| e.parse::<i32>().unwrap())
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::solver::{SolveError, Solver};
+
+pub fn parse(input: &str) -> Result<Vec<i32>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_number(i, line, line))
+        .collect()
+}
+
+pub fn part_one(input: &str) -> Result<i32, ParseError> {
+    Ok(resulting_frequency(&parse(input)?))
 }
 
-fn part_one(input: &str) -> i32 {
-    lines_of_i32_iterator(input).sum()
+pub fn part_two(input: &str) -> Result<i32, ParseError> {
+    Ok(first_repeated_frequency(&parse(input)?))
 }
 
-fn part_two(input: &str) -> i32 {
+fn resulting_frequency(changes: &[i32]) -> i32 {
+    changes.iter().sum()
+}
+
+fn first_repeated_frequency(changes: &[i32]) -> i32 {
     let mut seen = HashSet::new();
     let mut sum = 0;
-    for num in lines_of_i32_iterator(input).cycle() {
+    for num in changes.iter().cycle() {
         sum += num;
         if !seen.insert(sum) {
             return sum;
@@ -22,6 +36,25 @@ fn part_two(input: &str) -> i32 {
     unreachable!();
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<i32>;
+
+    fn parse(&self, input: &str) -> Result<Vec<i32>, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, changes: &Vec<i32>) -> Result<Answer, SolveError> {
+        Ok(resulting_frequency(changes).into())
+    }
+
+    fn part_two(&self, changes: &Vec<i32>) -> Result<Answer, SolveError> {
+        Ok(first_repeated_frequency(changes).into())
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -29,11 +62,11 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT), 543);
+        assert_eq!(part_one(INPUT).unwrap(), 543);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(part_two(INPUT), 621);
+        assert_eq!(part_two(INPUT).unwrap(), 621);
     }
 }
@@ -1,7 +1,9 @@
-#![cfg(test)]
-
 use std::collections::HashMap;
 
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::solver::{Error, SolveError, Solver};
+
 fn part_one_counts(id: &str) -> (bool, bool) {
     let mut counts = HashMap::new();
     for ch in id.chars() {
@@ -18,38 +20,95 @@ fn part_one_counts(id: &str) -> (bool, bool) {
     (has_count(2), has_count(3))
 }
 
-fn part_one(input: &str) -> u32 {
-    let (has2_count, has3_count) = input
-        .lines()
-        .map(part_one_counts)
+// Box IDs are lowercase letters, all the same length.
+pub fn parse(input: &str) -> Result<Vec<String>, ParseError> {
+    let mut ids: Vec<String> = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if let Some((index, _)) = line.char_indices().find(|(_, ch)| !ch.is_ascii_lowercase()) {
+            let text = &line[index..index + 1];
+            return Err(ParseError::new(
+                i,
+                line,
+                text,
+                "expected a lowercase letter",
+            ));
+        }
+        if ids.first().is_some_and(|first| first.len() != line.len()) {
+            return Err(ParseError::line(
+                i,
+                line,
+                format!("expected {} letters", ids[0].len()),
+            ));
+        }
+        ids.push(line.to_string());
+    }
+    Ok(ids)
+}
+
+pub fn part_one(input: &str) -> Result<u32, ParseError> {
+    Ok(checksum(&parse(input)?))
+}
+
+pub fn part_two(input: &str) -> Result<String, Error> {
+    Ok(common_letters(&parse(input)?)?)
+}
+
+fn checksum(ids: &[String]) -> u32 {
+    let (has2_count, has3_count) = ids
+        .iter()
+        .map(|id| part_one_counts(id))
         .fold((0, 0), |acc, elem| {
             (acc.0 + elem.0 as u32, acc.1 + elem.1 as u32)
         });
-    return has2_count * has3_count;
+    has2_count * has3_count
 }
 
-fn part_two(input: &str) -> String {
-    let codes: Vec<_> = input.lines().collect();
-
-    for (i, i_code) in codes.iter().enumerate() {
-        for j_code in codes.iter().skip(i + 1) {
-            assert_eq!(i_code.len(), j_code.len());
-            let different: Vec<_> = i_code
-                .chars()
-                .zip(j_code.chars())
+// Finds two IDs that differ in exactly one position, returning the first
+// and the position.
+fn close_pair(ids: &[String]) -> Option<(&str, usize)> {
+    ids.iter().enumerate().find_map(|(i, i_id)| {
+        ids.iter().skip(i + 1).find_map(|j_id| {
+            let mut different = i_id
+                .bytes()
+                .zip(j_id.bytes())
                 .enumerate()
-                .filter_map(|(index, (i_ch, j_ch))| if i_ch != j_ch { Some(index) } else { None })
-                .collect();
-            let mut ret = i_code.to_string();
-            if different.len() == 1 {
-                ret.remove(different[0]);
-                return ret;
+                .filter(|(_, (i_ch, j_ch))| i_ch != j_ch)
+                .map(|(index, _)| index);
+            match (different.next(), different.next()) {
+                (Some(index), None) => Some((i_id.as_str(), index)),
+                _ => None,
             }
-        }
+        })
+    })
+}
+
+fn common_letters(ids: &[String]) -> Result<String, SolveError> {
+    let (id, index) = close_pair(ids)
+        .ok_or_else(|| SolveError::new("expected two IDs that differ in one position"))?;
+    let mut common = id.to_string();
+    common.remove(index);
+    Ok(common)
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<String>;
+
+    fn parse(&self, input: &str) -> Result<Vec<String>, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, ids: &Vec<String>) -> Result<Answer, SolveError> {
+        Ok(checksum(ids).into())
+    }
+
+    fn part_two(&self, ids: &Vec<String>) -> Result<Answer, SolveError> {
+        Ok(common_letters(ids)?.into())
     }
-    unreachable!()
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -57,11 +116,32 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT), 4712);
+        assert_eq!(part_one(INPUT).unwrap(), 4712);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(part_two(INPUT), "lufjygedpvfbhftxiwnaorzmq");
+        assert_eq!(part_two(INPUT).unwrap(), "lufjygedpvfbhftxiwnaorzmq");
+    }
+
+    #[test]
+    fn test_part_one_example() {
+        let input = "abcdef\nbababc\nabbcde\nabcccd\naabcdd\nabcdee\nababab\n";
+        assert_eq!(part_one(input).unwrap(), 12);
+    }
+
+    #[test]
+    fn test_part_two_example() {
+        let input = "abcde\nfghij\nklmno\npqrst\nfguij\naxcye\nwvxyz\n";
+        assert_eq!(part_two(input).unwrap(), "fgij");
+        assert!(part_two("abc\nxyz\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse("abc\naBc\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 2, "B"));
+        let err = parse("abc\nabcd\n").unwrap_err();
+        assert_eq!(err.line, 2);
     }
 }
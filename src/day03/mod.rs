@@ -1,11 +1,12 @@
-#![cfg(test)]
-
-use std::collections::HashSet;
-
 use lazy_regex::regex_captures;
 
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::point::{Grid, Point2D, Rect};
+use crate::solver::{SolveError, Solver};
+
 #[derive(Debug)]
-struct Claim {
+pub struct Claim {
     id: u16,
     x: usize,
     y: usize,
@@ -14,69 +15,105 @@ struct Claim {
 }
 
 // Parse lines of the form: #1 @ 393,863: 11x29
-fn parse_claim(line: &str) -> Claim {
+fn parse_claim(i: usize, line: &str) -> Result<Claim, ParseError> {
     let (_, id, x, y, width, height) =
-        regex_captures!(r#"#(\d+) @ (\d+),(\d+): (\d+)x(\d+)"#, line).expect("bad input line");
-    Claim {
-        id: id.parse().unwrap(),
-        x: x.parse().unwrap(),
-        y: y.parse().unwrap(),
-        width: width.parse().unwrap(),
-        height: height.parse().unwrap(),
+        regex_captures!(r#"^#(\d+) @ (\d+),(\d+): (\d+)x(\d+)$"#, line.trim_end())
+            .ok_or_else(|| ParseError::line(i, line, "expected a claim like #1 @ 1,3: 4x4"))?;
+    let claim = Claim {
+        id: parse_number(i, line, id)?,
+        x: parse_number(i, line, x)?,
+        y: parse_number(i, line, y)?,
+        width: parse_number(i, line, width)?,
+        height: parse_number(i, line, height)?,
+    };
+    if claim.width == 0 {
+        return Err(ParseError::new(i, line, width, "expected a nonzero width"));
+    }
+    if claim.height == 0 {
+        return Err(ParseError::new(
+            i,
+            line,
+            height,
+            "expected a nonzero height",
+        ));
     }
+    Ok(claim)
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-enum ClaimedInch {
-    Available,
-    Disputed,
-    Claimed(u16),
+impl Claim {
+    fn rect(&self) -> Rect<usize> {
+        Rect::from_size(Point2D::new(self.x, self.y), self.width, self.height)
+    }
+}
+
+pub fn parse(input: &str) -> Result<Vec<Claim>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_claim(i, line))
+        .collect()
 }
 
-fn compute(input: &str) -> (usize, u16) {
-    let claims: Vec<Claim> = input.lines().map(parse_claim).collect();
-    let max_x = claims.iter().map(|c| c.x + c.width).max().unwrap();
-    let max_y = claims.iter().map(|c| c.y + c.height).max().unwrap();
-    let mut coverage = vec![ClaimedInch::Available; max_x * max_y];
-
-    let mut disputed_ids = HashSet::new();
-
-    for claim in claims.iter() {
-        for y in claim.y..(claim.y + claim.height) {
-            let start = max_x * y + claim.x;
-            let end = start + claim.width;
-            for c in &mut coverage[start..end] {
-                match c {
-                    ClaimedInch::Available => {
-                        *c = ClaimedInch::Claimed(claim.id);
-                    }
-                    ClaimedInch::Disputed => {
-                        disputed_ids.insert(claim.id);
-                    }
-                    ClaimedInch::Claimed(id) => {
-                        disputed_ids.insert(*id);
-                        disputed_ids.insert(claim.id);
-                        *c = ClaimedInch::Disputed;
-                    }
-                }
-            }
+// Returns the number of square inches claimed more than once and the ID of
+// the only claim that overlaps no other, if there is exactly one.
+pub fn compute(input: &str) -> Result<(usize, Option<u16>), ParseError> {
+    let claims = parse(input)?;
+    Ok((disputed_inches(&claims), undisputed_claim(&claims)))
+}
+
+// Counts the square inches within two or more claims.
+fn disputed_inches(claims: &[Claim]) -> usize {
+    let Some(bounds) = claims.iter().map(Claim::rect).reduce(|a, b| a.union(&b)) else {
+        return 0;
+    };
+
+    // Count the claims on each square inch.
+    let mut coverage = Grid::new(bounds.max.x + 1, bounds.max.y + 1, 0_u16);
+    for claim in claims {
+        for p in claim.rect().points() {
+            coverage[p] += 1;
         }
     }
+    coverage.values().filter(|&&count| count > 1).count()
+}
+
+// Returns the ID of the only claim that overlaps no other, or None if there
+// is no such claim or more than one.
+fn undisputed_claim(claims: &[Claim]) -> Option<u16> {
+    let rects: Vec<Rect<usize>> = claims.iter().map(Claim::rect).collect();
+    let mut undisputed = (0..rects.len()).filter(|&i| {
+        rects
+            .iter()
+            .enumerate()
+            .all(|(j, other)| i == j || rects[i].intersection(other).is_none())
+    });
+    match (undisputed.next(), undisputed.next()) {
+        (Some(i), None) => Some(claims[i].id),
+        _ => None,
+    }
+}
+
+pub struct Solution;
 
-    let all_ids = HashSet::from_iter(claims.iter().map(|c| c.id));
+impl Solver for Solution {
+    type Parsed = Vec<Claim>;
 
-    let disputed_count = coverage
-        .iter()
-        .filter(|c| **c == ClaimedInch::Disputed)
-        .count();
+    fn parse(&self, input: &str) -> Result<Vec<Claim>, ParseError> {
+        parse(input)
+    }
 
-    let undisputed_ids: Vec<_> = all_ids.difference(&disputed_ids).copied().collect();
-    assert_eq!(1, undisputed_ids.len());
-    let undisputed_id = undisputed_ids[0];
+    fn part_one(&self, claims: &Vec<Claim>) -> Result<Answer, SolveError> {
+        Ok(disputed_inches(claims).into())
+    }
 
-    (disputed_count, undisputed_id)
+    fn part_two(&self, claims: &Vec<Claim>) -> Result<Answer, SolveError> {
+        undisputed_claim(claims)
+            .map(Answer::from)
+            .ok_or_else(|| SolveError::new("expected exactly one claim that overlaps no other"))
+    }
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -84,8 +121,51 @@ mod tests {
 
     #[test]
     fn test() {
-        let (disputed_count, undisputed_id) = compute(INPUT);
+        let (disputed_count, undisputed_id) = compute(INPUT).unwrap();
         assert_eq!(disputed_count, 98005);
-        assert_eq!(undisputed_id, 331);
+        assert_eq!(undisputed_id, Some(331));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x99999999999999999999\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.column, 13);
+        assert_eq!(err.text, "99999999999999999999");
+
+        let err = parse("#1 @ 1,3: 4x4\n#2 @ 3,1 4x4\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.text, "#2 @ 3,1 4x4");
+
+        let err = parse("#1 @ 1,3: 4x0\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 13));
+
+        let err = parse("#1 @ 1,3: 4x4\ngarbage #1 @ 1,3: 4x4 trailing\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert!(parse("#1 @ 1,3: 4x4x4\n").is_err());
+    }
+
+    #[test]
+    fn test_example() {
+        let claims = parse("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2\n").unwrap();
+        assert_eq!(disputed_inches(&claims), 4);
+        assert_eq!(undisputed_claim(&claims), Some(3));
+    }
+
+    #[test]
+    fn test_no_single_undisputed_claim() {
+        assert_eq!(compute("").unwrap(), (0, None));
+        // Every claim overlaps another.
+        assert_eq!(
+            compute("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n").unwrap(),
+            (4, None)
+        );
+        // Two claims overlap no other.
+        assert_eq!(
+            compute("#1 @ 1,3: 4x4\n#2 @ 7,1: 4x4\n").unwrap(),
+            (0, None)
+        );
+        let claims = parse("#1 @ 1,3: 4x4\n#2 @ 7,1: 4x4\n").unwrap();
+        assert!(Solution.part_two(&claims).is_err());
     }
 }
@@ -1,19 +1,22 @@
-#![cfg(test)]
-
 use std::collections::HashMap;
 
 use itertools::Itertools;
 use lazy_regex::regex_captures;
 
-#[derive(Debug)]
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
+
+#[derive(Debug, Clone)]
 enum Action {
     BeginShift(u16),
     FallAsleep,
     WakeUp,
 }
 
-#[derive(Debug)]
-struct Event {
+#[derive(Debug, Clone)]
+pub struct Event {
     when: String,
     minute: u8,
     action: Action,
@@ -24,37 +27,83 @@ struct Event {
 // [1518-10-05 00:10] falls asleep
 // [1518-07-22 23:53] Guard #1949 begins shift
 // [1518-07-06 00:55] wakes up
-fn parse_line(line: &str) -> Event {
+fn parse_line(i: usize, line: &str) -> Result<Event, ParseError> {
     if let Some((_, when, minute, action, guard)) = regex_captures!(
         r#"^\[(\d+-\d+-\d+ \d+:(\d+))\] (falls asleep|wakes up|Guard #(\d+) begins shift)$"#,
         line
     ) {
-        Event {
+        Ok(Event {
             when: when.to_string(),
-            minute: minute.parse().unwrap(),
+            minute: parse_number(i, line, minute)?,
             action: if action == "falls asleep" {
                 Action::FallAsleep
             } else if action == "wakes up" {
                 Action::WakeUp
             } else {
-                Action::BeginShift(guard.parse().unwrap())
+                Action::BeginShift(parse_number(i, line, guard)?)
             },
-        }
+        })
     } else {
-        panic!("bad input line: {}", line.escape_debug())
+        Err(ParseError::line(i, line, "expected a guard event"))
+    }
+}
+
+// Returns the events in chronological order.
+pub fn parse(input: &str) -> Result<Vec<Event>, ParseError> {
+    let mut events = input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Ok((i, line, parse_line(i, line)?)))
+        .collect::<Result<Vec<_>, ParseError>>()?;
+    events.sort_unstable_by(|(_, _, a), (_, _, b)| a.when.cmp(&b.when));
+    match events.first() {
+        None => return Err(ParseError::line(0, "", "expected at least one event")),
+        Some((i, line, event)) if !matches!(event.action, Action::BeginShift(_)) => {
+            return Err(ParseError::line(
+                *i,
+                line,
+                "the earliest event must begin a shift",
+            ));
+        }
+        _ => {}
     }
+    // Every nap must end before the next event, later in the same hour, and
+    // some guard must take one for there to be a sleepiest guard.
+    let mut naps = 0;
+    for (k, (i, line, event)) in events.iter().enumerate() {
+        if !matches!(event.action, Action::FallAsleep) {
+            continue;
+        }
+        match events.get(k + 1) {
+            Some((_, _, next))
+                if matches!(next.action, Action::WakeUp) && next.minute > event.minute =>
+            {
+                naps += 1;
+            }
+            _ => {
+                return Err(ParseError::line(
+                    *i,
+                    line,
+                    "expected the guard to wake up later in the hour",
+                ));
+            }
+        }
+    }
+    if naps == 0 {
+        return Err(ParseError::line(0, "", "expected a guard to fall asleep"));
+    }
+    Ok(events.into_iter().map(|(_, _, event)| event).collect())
 }
 
-fn compute(input: &str) -> (usize, usize) {
-    let mut events: Vec<Event> = input.lines().map(parse_line).collect();
-    events.sort_unstable_by(|a, b| a.when.cmp(&b.when));
-    assert!(events.len() > 0);
-    assert!(matches!(events[0].action, Action::BeginShift(_)));
+// Map Dwarf ID to a histogram of sleep minutes.
+fn guard_sleep_histograms(events: Vec<Event>) -> HashMap<u16, Vec<u32>> {
     let tagged_events: Vec<(u16, Event)> = tag_events(events);
     let sleep_windows: Vec<(u16, u8, u8)> = sleep_windows(tagged_events);
+    sleep_histograms(sleep_windows)
+}
 
-    // Map Dwarf ID to a histogram of sleep minutes.
-    let sleep_histograms: HashMap<u16, Vec<u32>> = sleep_histograms(sleep_windows);
+pub fn compute(input: &str) -> Result<(usize, usize), ParseError> {
+    let sleep_histograms = guard_sleep_histograms(parse(input)?);
 
     // Strategy 1: Find the guard that has the most minutes asleep. What minute
     // does that guard spend asleep the most?
@@ -64,33 +113,34 @@ fn compute(input: &str) -> (usize, usize) {
     // same minute? What minute does that guard spend asleep the most?
     let answer2 = compute_strategy(&sleep_histograms, sleep_minutes_max);
 
-    (answer1, answer2)
+    Ok((answer1, answer2))
 }
 
 fn compute_strategy(
     sleep_histograms: &HashMap<u16, Vec<u32>>,
     max_strategy: fn(&[u32]) -> u32,
 ) -> usize {
-    let answer1 = {
-        let most_sleep: Option<(&u16, &Vec<u32>)> = {
-            sleep_histograms
-                .iter()
-                .max_by_key(|(_, histogram)| max_strategy(histogram))
-        };
-        let sleepiest_minute: Option<(usize, &u32)> = most_sleep
-            .unwrap()
-            .1
+    let most_sleep: Option<(&u16, &Vec<u32>)> = {
+        sleep_histograms
             .iter()
-            .enumerate()
-            .max_by_key(|(_i, c)| *c);
-
-        let (id, _) = most_sleep.unwrap();
-        let (minute, _) = sleepiest_minute.unwrap();
-        dbg!(&id);
-        dbg!(&minute);
-        (*id as usize) * minute
+            .max_by_key(|(_, histogram)| max_strategy(histogram))
     };
-    answer1
+    let sleepiest_minute: Option<(usize, &u32)> = most_sleep
+        .unwrap()
+        .1
+        .iter()
+        .enumerate()
+        .max_by_key(|(_i, c)| *c);
+
+    let (id, _) = most_sleep.unwrap();
+    let (minute, _) = sleepiest_minute.unwrap();
+    event!(
+        Level::Debug,
+        "guard {} sleeps most on minute {}",
+        id,
+        minute
+    );
+    (*id as usize) * minute
 }
 
 fn sleep_minutes_sum(histogram: &[u32]) -> u32 {
@@ -125,28 +175,26 @@ fn sleep_windows(tagged_events: Vec<(u16, Event)>) -> Vec<(u16, u8, u8)> {
     let sleep_windows: Vec<(u16, u8, u8)> = {
         let mut vec = Vec::new();
         for (a, b) in tagged_events.iter().tuple_windows() {
-            match (a, b) {
+            if let (
                 (
-                    (
-                        id_a,
-                        Event {
-                            when: _,
-                            minute: fall_asleep_minute,
-                            action: Action::FallAsleep,
-                        },
-                    ),
-                    (
-                        _id_b,
-                        Event {
-                            when: _,
-                            minute: wake_up_minute,
-                            action: Action::WakeUp,
-                        },
-                    ),
-                ) => {
-                    vec.push((*id_a, *fall_asleep_minute, *wake_up_minute));
-                }
-                _ => {}
+                    id_a,
+                    Event {
+                        when: _,
+                        minute: fall_asleep_minute,
+                        action: Action::FallAsleep,
+                    },
+                ),
+                (
+                    _id_b,
+                    Event {
+                        when: _,
+                        minute: wake_up_minute,
+                        action: Action::WakeUp,
+                    },
+                ),
+            ) = (a, b)
+            {
+                vec.push((*id_a, *fall_asleep_minute, *wake_up_minute));
             }
         }
         vec
@@ -168,6 +216,27 @@ fn tag_events(events: Vec<Event>) -> Vec<(u16, Event)> {
     tagged_events
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<Event>;
+
+    fn parse(&self, input: &str) -> Result<Vec<Event>, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, events: &Vec<Event>) -> Result<Answer, SolveError> {
+        let sleep_histograms = guard_sleep_histograms(events.clone());
+        Ok(compute_strategy(&sleep_histograms, sleep_minutes_sum).into())
+    }
+
+    fn part_two(&self, events: &Vec<Event>) -> Result<Answer, SolveError> {
+        let sleep_histograms = guard_sleep_histograms(events.clone());
+        Ok(compute_strategy(&sleep_histograms, sleep_minutes_max).into())
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -175,8 +244,32 @@ mod tests {
 
     #[test]
     fn test() {
-        let (answer1, answer2) = compute(INPUT);
+        let (answer1, answer2) = compute(INPUT).unwrap();
         assert_eq!(answer1, 21083);
         assert_eq!(answer2, 53024);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let err =
+            parse("[1518-11-01 00:00] Guard #10 begins shift\n[1518-11-01 00:05] dozes off\n")
+                .unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+
+        let err =
+            parse("[1518-11-01 00:05] falls asleep\n[1518-11-01 00:25] wakes up\n").unwrap_err();
+        assert_eq!(err.line, 1);
+        assert_eq!(err.text, "[1518-11-01 00:05] falls asleep");
+
+        assert!(parse("[1518-11-01 00:00] Guard #10 begins shift\n").is_err());
+        let err = parse(
+            "[1518-11-01 00:00] Guard #10 begins shift\n\
+             [1518-11-01 00:30] wakes up\n\
+             [1518-11-01 00:25] falls asleep\n\
+             [1518-11-02 00:00] Guard #99 begins shift\n\
+             [1518-11-02 00:40] falls asleep\n",
+        )
+        .unwrap_err();
+        assert_eq!(err.line, 5);
+    }
 }
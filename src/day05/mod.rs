@@ -1,48 +1,90 @@
-#![cfg(test)]
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::solver::{SolveError, Solver};
 
-fn reduce(input: &str, omit_unit: Option<u8>) -> usize {
-    let polymer = input.trim().as_bytes().to_vec();
-    let reduced = polymer.iter().fold(Vec::new(), |mut reduced, &next_unit| {
+fn reduce(polymer: &str, omit_unit: Option<u8>) -> usize {
+    let polymer = polymer.as_bytes();
+    let reduced: Vec<u8> = polymer.iter().fold(Vec::new(), |mut reduced, &next_unit| {
         if omit_unit
-            .map(|omit_unit| omit_unit.to_ascii_lowercase() == next_unit.to_ascii_lowercase())
+            .map(|omit_unit| omit_unit.eq_ignore_ascii_case(&next_unit))
             .unwrap_or(false)
         {
             return reduced;
         }
         match reduced.last().copied() {
-            None => {
-                reduced.push(next_unit);
-                return reduced;
+            Some(prev_unit)
+                if prev_unit.eq_ignore_ascii_case(&next_unit) && prev_unit != next_unit =>
+            {
+                // React by destroying both units.
+                reduced.pop();
             }
-            Some(prev_unit) => {
-                if prev_unit.to_ascii_lowercase() == next_unit.to_ascii_lowercase()
-                    && prev_unit != next_unit
-                {
-                    // React by destroying both units.
-                    reduced.pop();
-                    return reduced;
-                } else {
-                    reduced.push(next_unit);
-                    return reduced;
-                }
+            _ => {
+                reduced.push(next_unit);
             }
         }
+        reduced
     });
 
     reduced.len()
 }
 
-fn part_one(input: &str) -> usize {
-    reduce(input, None)
+// The polymer is a single line of letters.
+pub fn parse(input: &str) -> Result<String, ParseError> {
+    let mut polymer: Option<&str> = None;
+    for (i, line) in input.lines().enumerate() {
+        let units = line.trim();
+        if units.is_empty() {
+            continue;
+        }
+        if polymer.is_some() {
+            return Err(ParseError::line(i, line, "expected a single line"));
+        }
+        if let Some((index, ch)) = units
+            .char_indices()
+            .find(|(_, ch)| !ch.is_ascii_alphabetic())
+        {
+            let text = &units[index..index + ch.len_utf8()];
+            return Err(ParseError::new(i, line, text, "expected a letter"));
+        }
+        polymer = Some(units);
+    }
+    Ok(polymer.unwrap_or_default().to_string())
+}
+
+pub fn part_one(input: &str) -> Result<usize, ParseError> {
+    Ok(reduce(&parse(input)?, None))
+}
+
+pub fn part_two(input: &str) -> Result<usize, ParseError> {
+    Ok(shortest_with_unit_removed(&parse(input)?))
 }
 
-fn part_two(input: &str) -> usize {
+fn shortest_with_unit_removed(polymer: &str) -> usize {
     (b'a'..=b'z')
-        .map(|unit| reduce(input, Some(unit)))
+        .map(|unit| reduce(polymer, Some(unit)))
         .min()
         .unwrap()
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = String;
+
+    fn parse(&self, input: &str) -> Result<String, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, polymer: &String) -> Result<Answer, SolveError> {
+        Ok(reduce(polymer, None).into())
+    }
+
+    fn part_two(&self, polymer: &String) -> Result<Answer, SolveError> {
+        Ok(shortest_with_unit_removed(polymer).into())
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -50,11 +92,11 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT), 10878);
+        assert_eq!(part_one(INPUT).unwrap(), 10878);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(part_two(INPUT), 6874);
+        assert_eq!(part_two(INPUT).unwrap(), 6874);
     }
 }
@@ -1,22 +1,34 @@
-use std::{
-    cmp::Ordering,
-    collections::{HashSet, VecDeque},
-};
+use std::cmp::Ordering;
 
-use crate::point::Point2D;
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::point::{Point2D, Rect};
+use crate::search;
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
 
 type Point = Point2D<i32>;
 
-fn parse_points(input: &str) -> Vec<Point> {
-    let mut points: Vec<Point> = input
+// Parse lines of the form: 194, 200
+pub fn parse_points(input: &str) -> Result<Vec<Point>, ParseError> {
+    let mut points = input
         .lines()
-        .map(|line| {
-            let (x, y) = line.split_once(", ").unwrap();
-            Point::new(x.parse().unwrap(), y.parse().unwrap())
+        .enumerate()
+        .map(|(i, line)| {
+            let (x, y) = line
+                .split_once(", ")
+                .ok_or_else(|| ParseError::line(i, line, "expected a point like 1, 2"))?;
+            Ok(Point::new(
+                parse_number(i, line, x)?,
+                parse_number(i, line, y)?,
+            ))
         })
-        .collect();
+        .collect::<Result<Vec<Point>, ParseError>>()?;
+    if points.is_empty() {
+        return Err(ParseError::line(0, "", "expected at least one point"));
+    }
     points.sort();
-    points
+    Ok(points)
 }
 
 #[derive(Debug)]
@@ -25,101 +37,97 @@ enum Claim {
     Tie(i32),
 }
 
-pub fn part_one(input: &str) -> i32 {
-    let points = parse_points(input);
+pub fn part_one(input: &str) -> Result<i32, ParseError> {
+    Ok(largest_finite_area(&parse_points(input)?))
+}
 
-    let (min, max) = bounds(&points);
+fn largest_finite_area(points: &[Point]) -> i32 {
+    let bounds = Rect::from_points(points.iter().copied()).unwrap();
 
     let mut claim_counts = vec![0; points.len()];
-    for y in min.y..=max.y {
-        for x in min.x..=max.x {
-            let here = Point::new(x, y);
-            let initial = Claim::Min(here.manhattan_distance(points[0]), 0);
-            let min = points
-                .iter()
-                .enumerate()
-                .skip(1)
-                .fold(initial, |claim, (i, point)| {
-                    let claim_dist = match claim {
-                        Claim::Min(dist, _) => dist,
-                        Claim::Tie(dist) => dist,
-                    };
-                    let dist = here.manhattan_distance(*point);
-                    match dist.cmp(&claim_dist) {
-                        Ordering::Less => Claim::Min(dist, i),
-                        Ordering::Equal => Claim::Tie(dist),
-                        Ordering::Greater => claim,
-                    }
-                });
-            if let Claim::Min(_, index) = min {
-                claim_counts[index] += 1;
-            }
+    for here in bounds.points() {
+        let initial = Claim::Min(here.manhattan_distance(points[0]), 0);
+        let min = points
+            .iter()
+            .enumerate()
+            .skip(1)
+            .fold(initial, |claim, (i, point)| {
+                let claim_dist = match claim {
+                    Claim::Min(dist, _) => dist,
+                    Claim::Tie(dist) => dist,
+                };
+                let dist = here.manhattan_distance(*point);
+                match dist.cmp(&claim_dist) {
+                    Ordering::Less => Claim::Min(dist, i),
+                    Ordering::Equal => Claim::Tie(dist),
+                    Ordering::Greater => claim,
+                }
+            });
+        if let Claim::Min(_, index) = min {
+            claim_counts[index] += 1;
         }
     }
 
     let max_claim_count = claim_counts
         .iter()
         .enumerate()
-        .filter(|(index, _)| {
-            let point = points[*index];
-            point.x != min.x && point.x != max.x && point.y != min.y && point.y != max.y
-        })
+        .filter(|(index, _)| !bounds.on_edge(points[*index]))
         .map(|(_, count)| *count)
         .max()
         .unwrap();
 
-    dbg!((max.x - min.x) * (max.y - min.y));
-    dbg!(claim_counts.iter().sum::<i32>());
-    dbg!(&max_claim_count);
+    event!(
+        Level::Debug,
+        "bounds area {}, claimed {}, largest finite area {}",
+        bounds.area(),
+        claim_counts.iter().sum::<i32>(),
+        max_claim_count
+    );
 
     max_claim_count
 }
 
-pub fn part_two(input: &str) -> usize {
-    let points = parse_points(input);
+pub fn part_two(input: &str) -> Result<usize, ParseError> {
+    Ok(safe_region_size(&parse_points(input)?))
+}
+
+fn safe_region_size(points: &[Point]) -> usize {
+    let in_region = |here: &Point| {
+        points
+            .iter()
+            .map(|there| here.manhattan_distance(*there))
+            .sum::<i32>()
+            < 10_000
+    };
+
+    // The region is around the points, so it is reachable from the points
+    // within their bounds.
+    let bounds = Rect::from_points(points.iter().copied()).unwrap();
+    let starts = bounds.points().filter(in_region);
+    let region = search::bfs(
+        starts,
+        |here| here.neighbors().filter(in_region).collect::<Vec<_>>(),
+        |_| false,
+    );
+    region.distances().len()
+}
 
-    let mut queue = VecDeque::new();
-    let mut seen = HashSet::new();
+pub struct Solution;
 
-    let (min, max) = bounds(&points);
+impl Solver for Solution {
+    type Parsed = Vec<Point>;
 
-    for y in min.y..=max.y {
-        for x in min.x..=max.x {
-            let here = Point::new(x, y);
-            seen.insert(here);
-            queue.push_back(here);
-        }
+    fn parse(&self, input: &str) -> Result<Vec<Point>, ParseError> {
+        parse_points(input)
     }
 
-    let mut region_count = 0;
-    while let Some(here) = queue.pop_front() {
-        let sum = points
-            .iter()
-            .map(|there| here.manhattan_distance(*there))
-            .sum::<i32>();
-        if sum < 10_000 {
-            region_count += 1;
-            for neighbor in here.neighbors() {
-                if seen.insert(neighbor) {
-                    queue.push_back(neighbor);
-                }
-            }
-        }
+    fn part_one(&self, points: &Vec<Point>) -> Result<Answer, SolveError> {
+        Ok(largest_finite_area(points).into())
     }
 
-    region_count
-}
-
-fn bounds(points: &[Point]) -> (Point, Point) {
-    let mut min = points[0];
-    let mut max = points[0];
-    for p in points.iter() {
-        min.x = min.x.min(p.x);
-        min.y = min.y.min(p.y);
-        max.x = max.x.max(p.x);
-        max.y = max.y.max(p.y);
+    fn part_two(&self, points: &Vec<Point>) -> Result<Answer, SolveError> {
+        Ok(safe_region_size(points).into())
     }
-    (min, max)
 }
 
 #[cfg(test)]
@@ -130,11 +138,11 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        assert_eq!(part_one(INPUT), 4475);
+        assert_eq!(part_one(INPUT).unwrap(), 4475);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(part_two(INPUT), 35237);
+        assert_eq!(part_two(INPUT).unwrap(), 35237);
     }
 }
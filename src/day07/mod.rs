@@ -3,26 +3,37 @@ use std::{
     collections::{BTreeMap, BinaryHeap},
 };
 
+use lazy_regex::regex_captures;
+
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
+
 // Return two tasks (prerequisite, dependent) where the prerequisite
-// task must be completed before the dependent.
-fn parse_line(line: &str) -> (u8, u8) {
-    let parsed: Vec<u8> = line
-        .split_whitespace()
-        .filter_map(|s| {
-            if s.len() == 1 && s.as_bytes()[0].is_ascii_uppercase() {
-                Some(s.as_bytes()[0] - b'A')
-            } else {
-                None
-            }
-        })
-        .collect();
-    assert_eq!(2, parsed.len());
-    (parsed[0], parsed[1])
+// task must be completed before the dependent, from a line of the form:
+// Step C must be finished before step A can begin.
+fn parse_line(i: usize, line: &str) -> Result<(u8, u8), ParseError> {
+    let (_, prerequisite, dependent) = regex_captures!(
+        r#"^Step ([A-Z]) must be finished before step ([A-Z]) can begin\.$"#,
+        line.trim_end()
+    )
+    .ok_or_else(|| {
+        ParseError::line(
+            i,
+            line,
+            "expected Step X must be finished before step Y can begin.",
+        )
+    })?;
+    Ok((
+        prerequisite.as_bytes()[0] - b'A',
+        dependent.as_bytes()[0] - b'A',
+    ))
 }
 
 // Return a map of dependent tasks [0..26) mapped to a bitmask of prerequisite
-// tasks.
-fn parse(input: &str) -> BTreeMap<u8, u32> {
+// tasks.  Fails if a task depends on itself, directly or through others.
+pub fn parse(input: &str) -> Result<BTreeMap<u8, u32>, ParseError> {
     let mut task_prerequisites = BTreeMap::new();
 
     // Populate all keys [0..26) with zero.
@@ -30,23 +41,57 @@ fn parse(input: &str) -> BTreeMap<u8, u32> {
         task_prerequisites.entry(task).or_default();
     }
 
-    for line in input.lines() {
-        let (prerequisite, dependent) = parse_line(line);
-        let prerequisite = 1_u32 << prerequisite;
+    let mut edges = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let (prerequisite, dependent) = parse_line(i, line)?;
+        if prerequisite == dependent {
+            return Err(ParseError::line(i, line, "a step cannot depend on itself"));
+        }
+        edges.push((i, line, prerequisite, dependent));
         task_prerequisites
             .entry(dependent)
-            .and_modify(|p| *p |= prerequisite);
+            .and_modify(|p| *p |= 1_u32 << prerequisite);
     }
-    task_prerequisites
+
+    // Complete every task whose prerequisites are complete until none is
+    // left to start.  Any task left over is on a cycle or waits on one.
+    let mut completed = 0_u32;
+    while let Some(task) = task_prerequisites
+        .iter()
+        .find_map(|(&task, &prerequisites)| {
+            (completed & 1 << task == 0 && completed & prerequisites == prerequisites)
+                .then_some(task)
+        })
+    {
+        completed |= 1 << task;
+    }
+    let left_over = |task: u8| completed & 1 << task == 0;
+    if let Some(&(i, line, _, dependent)) = edges
+        .iter()
+        .find(|&&(_, _, prerequisite, dependent)| left_over(prerequisite) && left_over(dependent))
+    {
+        return Err(ParseError::line(
+            i,
+            line,
+            format!(
+                "step {} is never ready to begin",
+                (b'A' + dependent) as char
+            ),
+        ));
+    }
+    Ok(task_prerequisites)
+}
+
+pub fn compute_part_one(input: &str) -> Result<String, ParseError> {
+    Ok(completion_order(parse(input)?))
 }
 
-pub fn compute_part_one(input: &str) -> String {
-    let mut task_prerequisites = parse(input);
-    dbg!(&task_prerequisites);
+fn completion_order(mut task_prerequisites: BTreeMap<u8, u32>) -> String {
     let mut completed = 0_u32;
     let mut result = String::new();
     while !task_prerequisites.is_empty() {
-        dbg!(&task_prerequisites);
+        event!(Level::Trace, "prerequisites: {:?}", task_prerequisites);
+        // Some task is ready, as parsing rejects cycles.
         let task = *task_prerequisites
             .iter()
             .find_map(|(task, prerequisites)| {
@@ -57,7 +102,7 @@ pub fn compute_part_one(input: &str) -> String {
                 }
             })
             .unwrap();
-        dbg!(task);
+        event!(Level::Debug, "step {}", (b'A' + task) as char);
         completed |= 1_u32 << task;
         task_prerequisites.remove(&task);
         result.push((b'A' + task) as char);
@@ -71,8 +116,11 @@ struct Task {
     task_index: u8,
 }
 
-pub fn compute_part_two(input: &str) -> u32 {
-    let mut task_prerequisites = parse(input);
+pub fn compute_part_two(input: &str) -> Result<u32, ParseError> {
+    Ok(assembly_time(parse(input)?))
+}
+
+fn assembly_time(mut task_prerequisites: BTreeMap<u8, u32>) -> u32 {
     let mut completed = 0_u32;
     let mut working: BinaryHeap<Reverse<Task>> = BinaryHeap::new();
     let mut now_sec = 0_u32;
@@ -126,6 +174,24 @@ pub fn compute_part_two(input: &str) -> u32 {
     now_sec
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = BTreeMap<u8, u32>;
+
+    fn parse(&self, input: &str) -> Result<BTreeMap<u8, u32>, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, task_prerequisites: &BTreeMap<u8, u32>) -> Result<Answer, SolveError> {
+        Ok(completion_order(task_prerequisites.clone()).into())
+    }
+
+    fn part_two(&self, task_prerequisites: &BTreeMap<u8, u32>) -> Result<Answer, SolveError> {
+        Ok(assembly_time(task_prerequisites.clone()).into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,11 +200,42 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        assert_eq!(compute_part_one(INPUT), "BITRAQVSGUWKXYHMZPOCDLJNFE");
+        assert_eq!(
+            compute_part_one(INPUT).unwrap(),
+            "BITRAQVSGUWKXYHMZPOCDLJNFE"
+        );
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(compute_part_two(INPUT), 869);
+        assert_eq!(compute_part_two(INPUT).unwrap(), 869);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let input = "\
+Step C must be finished before step A can begin.
+Step C must be finished before step begin.
+";
+        let err = parse(input).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.text, "Step C must be finished before step begin.");
+
+        let err =
+            parse("garbage Step C must be finished before step A can begin. x\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        assert!(parse("Step C must be finished before step AB can begin.\n").is_err());
+
+        let err = parse("Step B must be finished before step B can begin.\n").unwrap_err();
+        assert_eq!(err.line, 1);
+
+        let input = "\
+Step C must be finished before step A can begin.
+Step A must be finished before step B can begin.
+Step B must be finished before step A can begin.
+";
+        let err = parse(input).unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.message, "step B is never ready to begin");
     }
 }
@@ -1,5 +1,48 @@
-fn compute(input: &str, compute: fn(&mut dyn Iterator<Item = i32>) -> i32) -> i32 {
-    let mut it = input.split_whitespace().map(|s| s.parse::<i32>().unwrap());
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::solver::{SolveError, Solver};
+
+pub fn parse(input: &str) -> Result<Vec<i32>, ParseError> {
+    let mut numbers = Vec::new();
+    let mut last_line = (0, "");
+    for (i, line) in input.lines().enumerate() {
+        for token in line.split_whitespace() {
+            numbers.push(parse_number::<u16>(i, line, token)?.into());
+        }
+        if !line.trim().is_empty() {
+            last_line = (i, line);
+        }
+    }
+    match node_end(&numbers, 0) {
+        Some(end) if end == numbers.len() => Ok(numbers),
+        Some(_) => Err(ParseError::line(
+            last_line.0,
+            last_line.1,
+            "unexpected numbers after the root node",
+        )),
+        None => Err(ParseError::line(
+            last_line.0,
+            last_line.1,
+            "input ends before the root node does",
+        )),
+    }
+}
+
+// Returns the index just past the node starting at `start`, or None if the
+// numbers run out first.
+fn node_end(numbers: &[i32], start: usize) -> Option<usize> {
+    let child_count = *numbers.get(start)?;
+    let metadata_count = *numbers.get(start + 1)? as usize;
+    let mut end = start + 2;
+    for _ in 0..child_count {
+        end = node_end(numbers, end)?;
+    }
+    end += metadata_count;
+    (end <= numbers.len()).then_some(end)
+}
+
+fn compute(numbers: &[i32], compute: fn(&mut dyn Iterator<Item = i32>) -> i32) -> i32 {
+    let mut it = numbers.iter().copied();
     compute(&mut it)
 }
 
@@ -37,12 +80,30 @@ fn node_value(it: &mut dyn Iterator<Item = i32>) -> i32 {
     }
 }
 
-pub fn compute_part_one(input: &str) -> i32 {
-    compute(input, sum_metadata)
+pub fn compute_part_one(input: &str) -> Result<i32, ParseError> {
+    Ok(compute(&parse(input)?, sum_metadata))
+}
+
+pub fn compute_part_two(input: &str) -> Result<i32, ParseError> {
+    Ok(compute(&parse(input)?, node_value))
 }
 
-pub fn compute_part_two(input: &str) -> i32 {
-    compute(input, node_value)
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<i32>;
+
+    fn parse(&self, input: &str) -> Result<Vec<i32>, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, numbers: &Vec<i32>) -> Result<Answer, SolveError> {
+        Ok(compute(numbers, sum_metadata).into())
+    }
+
+    fn part_two(&self, numbers: &Vec<i32>) -> Result<Answer, SolveError> {
+        Ok(compute(numbers, node_value).into())
+    }
 }
 
 #[cfg(test)]
@@ -53,11 +114,20 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        assert_eq!(compute_part_one(INPUT), 45194);
+        assert_eq!(compute_part_one(INPUT).unwrap(), 45194);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(compute_part_two(INPUT), 22989);
+        assert_eq!(compute_part_two(INPUT).unwrap(), 22989);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(parse("0 1 7\n").unwrap(), vec![0, 1, 7]);
+        assert!(parse("1 1 0 1 7\n").is_err());
+        assert!(parse("0 1 7 8\n").is_err());
+        let err = parse("0 1 -7\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 5));
     }
 }
@@ -1,5 +1,11 @@
 use std::collections::VecDeque;
 
+use lazy_regex::regex_captures;
+
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::solver::{SolveError, Solver};
+
 #[derive(Default, Debug, Clone)]
 struct Circle {
     // Clockwise is from front to back. The current marble is at the
@@ -29,6 +35,33 @@ impl Circle {
     }
 }
 
+// Parse input of the form:
+// 493 players; last marble is worth 71863 points
+pub fn parse(input: &str) -> Result<(usize, i64), ParseError> {
+    let (i, line) = input
+        .lines()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())
+        .unwrap_or_default();
+    let (_, players, last_marble) = regex_captures!(
+        r#"^(\d+) players; last marble is worth (\d+) points$"#,
+        line.trim()
+    )
+    .ok_or_else(|| {
+        ParseError::line(i, line, "expected N players; last marble is worth M points")
+    })?;
+    let player_count: usize = parse_number(i, line, players)?;
+    if player_count == 0 {
+        return Err(ParseError::new(
+            i,
+            line,
+            players,
+            "expected at least one player",
+        ));
+    }
+    Ok((player_count, parse_number(i, line, last_marble)?))
+}
+
 pub fn compute_part_one(players: usize, last_marble: i64) -> i64 {
     let mut circle = Circle::default();
     circle.place(0);
@@ -50,6 +83,25 @@ pub fn compute_part_one(players: usize, last_marble: i64) -> i64 {
     scores.iter().max().copied().unwrap()
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = (usize, i64);
+
+    fn parse(&self, input: &str) -> Result<(usize, i64), ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, &(players, last_marble): &(usize, i64)) -> Result<Answer, SolveError> {
+        Ok(compute_part_one(players, last_marble).into())
+    }
+
+    fn part_two(&self, &(players, last_marble): &(usize, i64)) -> Result<Answer, SolveError> {
+        // Part two asks for a last marble 100 times larger.
+        Ok(compute_part_one(players, last_marble * 100).into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,6 +109,27 @@ mod tests {
     const INPUT_PLAYERS: usize = 493;
     const INPUT_LAST_MARBLE: i64 = 71863;
 
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("493 players; last marble is worth 71863 points\n"),
+            Ok((INPUT_PLAYERS, INPUT_LAST_MARBLE))
+        );
+        assert_eq!(
+            parse("\n\n9 players; last marble is worth 25 points\n"),
+            Ok((9, 25))
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse("\n0 players; last marble is worth 25 points\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 1, "0"));
+        let err = parse("\n9 players; last marble is worth x points\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert!(parse("").is_err());
+    }
+
     #[test]
     fn test() {
         assert_eq!(compute_part_one(9, 32), 32);
@@ -1,14 +1,15 @@
-use std::collections::HashSet;
-
-use itertools::{Itertools, MinMaxResult};
 use lazy_regex::regex_captures;
 
-use crate::point::Point2D;
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::point::{Point2D, Rect, SparseGrid};
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
 
 type Point = Point2D<i32>;
 
 #[derive(Debug, Clone, Copy)]
-struct MovingPoint {
+pub struct MovingPoint {
     position: Point,
     velocity: Point,
 }
@@ -24,35 +25,33 @@ impl MovingPoint {
 
 // Parse lines of the form:
 // position=< 21188,  31669> velocity=<-2, -3>
-fn parse_line(line: &str) -> MovingPoint {
+fn parse_line(i: usize, line: &str) -> Result<MovingPoint, ParseError> {
     let (_, px, py, dx, dy) = regex_captures!(
         r#"position=<\s*(-?\d+)\s*,\s*(-?\d+)\s*>\s*velocity=<\s*(-?\d+)\s*,\s*(-?\d+)\s*>"#,
         line
     )
-    .unwrap();
-
-    MovingPoint {
-        position: Point::new(px.parse().unwrap(), py.parse().unwrap()),
-        velocity: Point::new(dx.parse().unwrap(), dy.parse().unwrap()),
-    }
-}
+    .ok_or_else(|| ParseError::line(i, line, "expected position=<x, y> velocity=<x, y>"))?;
 
-fn parse_lines(input: &str) -> Vec<MovingPoint> {
-    input.lines().map(parse_line).collect()
+    Ok(MovingPoint {
+        position: Point::new(parse_number(i, line, px)?, parse_number(i, line, py)?),
+        velocity: Point::new(parse_number(i, line, dx)?, parse_number(i, line, dy)?),
+    })
 }
 
-fn compute_bounds(points: &[MovingPoint]) -> (Point, Point) {
-    let x = points.iter().map(|e| e.position.x).minmax();
-    let y = points.iter().map(|e| e.position.y).minmax();
-    if let (MinMaxResult::MinMax(min_x, max_x), MinMaxResult::MinMax(min_y, max_y)) = (x, y) {
-        (Point::new(min_x, min_y), Point::new(max_x, max_y))
-    } else {
-        unreachable!()
+pub fn parse_lines(input: &str) -> Result<Vec<MovingPoint>, ParseError> {
+    let points = input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_line(i, line))
+        .collect::<Result<Vec<_>, _>>()?;
+    if points.len() < 2 {
+        return Err(ParseError::line(0, "", "expected at least two points"));
     }
+    Ok(points)
 }
 
 fn compute_area(points: &[MovingPoint]) -> i64 {
-    let (min, max) = compute_bounds(points);
+    let Rect { min, max } = Rect::from_points(points.iter().map(|p| p.position)).unwrap();
     (max.x - min.x) as i64 * (max.y - min.y) as i64
 }
 
@@ -61,28 +60,20 @@ fn step(points: &[MovingPoint]) -> Vec<MovingPoint> {
 }
 
 fn format_points(points: &[MovingPoint]) -> String {
-    let (min, max) = compute_bounds(points);
-    dbg!(min, max);
-
-    let positions: HashSet<Point> = HashSet::from_iter(points.iter().map(|p| p.position));
-
-    let mut s = String::new();
-    for y in min.y..=max.y {
-        for x in min.x..=max.x {
-            let ch = if positions.contains(&Point::new(x, y)) {
-                'X'
-            } else {
-                '.'
-            };
-            s.push(ch);
-        }
-        s.push('\n');
+    let grid: SparseGrid<()> = points.iter().map(|p| (p.position, ())).collect();
+    if let Some(bounds) = grid.bounds() {
+        event!(Level::Debug, "message bounds {}", bounds);
     }
-    s
+    grid.render(|cell| if cell.is_some() { 'X' } else { '.' })
+}
+
+pub fn compute(input: &str) -> Result<(String, i32), ParseError> {
+    Ok(align(parse_lines(input)?))
 }
 
-pub fn compute(input: &str) -> (String, i32) {
-    let mut points = parse_lines(input);
+// Step the points until the area of their bounding box stops shrinking,
+// returning the message and the number of seconds it took to appear.
+fn align(mut points: Vec<MovingPoint>) -> (String, i32) {
     let mut area = compute_area(&points);
     let mut seconds = 0;
     loop {
@@ -99,6 +90,24 @@ pub fn compute(input: &str) -> (String, i32) {
     (format_points(&points), seconds)
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<MovingPoint>;
+
+    fn parse(&self, input: &str) -> Result<Vec<MovingPoint>, ParseError> {
+        parse_lines(input)
+    }
+
+    fn part_one(&self, points: &Vec<MovingPoint>) -> Result<Answer, SolveError> {
+        Ok(Answer::Art(align(points.clone()).0))
+    }
+
+    fn part_two(&self, points: &Vec<MovingPoint>) -> Result<Answer, SolveError> {
+        Ok(align(points.clone()).1.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,7 +128,7 @@ X....X..X....X..X.......X....X..X....X..X....X..X...X...X.....
 X....X..X.......X.......X....X..X....X..X....X..X...X...X.....
 X....X..X.......X.......X....X..X....X..X....X..X....X..XXXXXX
 ";
-        let (answer, seconds) = compute(INPUT);
+        let (answer, seconds) = compute(INPUT).unwrap();
         assert_eq!(answer, expected_answer.trim_start());
         assert_eq!(seconds, 10521);
     }
@@ -1,5 +1,10 @@
 use std::ops::RangeInclusive;
 
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_single_number};
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
+
 fn power_level(x: i32, y: i32, serial_number: i32) -> i32 {
     let rack_id = x + 10;
     let mut power_level = rack_id * y;
@@ -29,9 +34,8 @@ fn compute_sums(serial_number: i32) -> Sums {
     sums
 }
 
-pub fn compute(serial_number: i32, sizes: RangeInclusive<usize>) -> String {
-    dbg!(serial_number);
-
+// Returns the 1-based (x, y, size) of the square with the largest total power.
+pub fn compute(serial_number: i32, sizes: RangeInclusive<usize>) -> (usize, usize, usize) {
     let sums = compute_sums(serial_number);
 
     let total_power = |x: usize, y: usize, size: usize| {
@@ -46,8 +50,37 @@ pub fn compute(serial_number: i32, sizes: RangeInclusive<usize>) -> String {
         })
         .max_by_key(|(sum, _, _, _)| *sum)
         .unwrap();
-    dbg!(sum, x, y, size);
-    format!("{},{},{}", x + 1, y + 1, size)
+    event!(
+        Level::Debug,
+        "serial {}: power {} at {},{} size {}",
+        serial_number,
+        sum,
+        x + 1,
+        y + 1,
+        size
+    );
+    (x + 1, y + 1, size)
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = i32;
+
+    fn parse(&self, input: &str) -> Result<i32, ParseError> {
+        parse_single_number(input)
+    }
+
+    fn part_one(&self, serial_number: &i32) -> Result<Answer, SolveError> {
+        // The site wants only "x,y" for part one.
+        let (x, y, _size) = compute(*serial_number, 3..=3);
+        Ok(Answer::coordinate([x, y]))
+    }
+
+    fn part_two(&self, serial_number: &i32) -> Result<Answer, SolveError> {
+        let (x, y, size) = compute(*serial_number, 1..=COORDINATE_MAX);
+        Ok(Answer::coordinate([x, y, size]))
+    }
 }
 
 #[cfg(test)]
@@ -66,19 +99,19 @@ mod tests {
 
     #[test]
     fn test_examples() {
-        assert_eq!(compute(18, 3..=3), "33,45,3");
-        assert_eq!(compute(42, 3..=3), "21,61,3");
+        assert_eq!(compute(18, 3..=3), (33, 45, 3));
+        assert_eq!(compute(42, 3..=3), (21, 61, 3));
     }
 
     #[test]
     fn test_part_one() {
         // Paste only "243,72" into adventofcode.org
-        assert_eq!(compute(INPUT, 3..=3), "243,72,3");
+        assert_eq!(compute(INPUT, 3..=3), (243, 72, 3));
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(compute(INPUT, 1..=COORDINATE_MAX), "229,192,11");
+        assert_eq!(compute(INPUT, 1..=COORDINATE_MAX), (229, 192, 11));
     }
 
     // For part two: https://en.wikipedia.org/wiki/Summed-area_table.
@@ -1,31 +1,68 @@
 use std::collections::HashSet;
 
-fn parse_state(input: &str) -> Vec<bool> {
-    Vec::from_iter(input.chars().map(|ch| match ch {
-        '#' => true,
-        '.' => false,
-        _ => panic!("invalid input"),
-    }))
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::solver::{SolveError, Solver};
+
+fn parse_state(i: usize, line: &str, unparsed_state: &str) -> Result<Vec<bool>, ParseError> {
+    unparsed_state
+        .char_indices()
+        .map(|(index, ch)| match ch {
+            '#' => Ok(true),
+            '.' => Ok(false),
+            _ => Err(ParseError::new(
+                i,
+                line,
+                &unparsed_state[index..index + ch.len_utf8()],
+                "expected '#' or '.'",
+            )),
+        })
+        .collect()
 }
 
-fn parse(input: &str) -> (Vec<bool>, HashSet<Vec<bool>>) {
-    let mut state = Vec::new();
+pub fn parse(input: &str) -> Result<(Vec<bool>, HashSet<Vec<bool>>), ParseError> {
+    let mut state = None;
     let mut notes: HashSet<Vec<bool>> = HashSet::new();
-    for line in input.lines() {
+    for (i, line) in input.lines().enumerate() {
         if let Some(unparsed_state) = line.strip_prefix("initial state: ") {
-            state = parse_state(unparsed_state);
+            if state.is_some() {
+                return Err(ParseError::line(i, line, "second initial state"));
+            }
+            let pots = parse_state(i, line, unparsed_state)?;
+            if pots.is_empty() {
+                return Err(ParseError::line(i, line, "expected at least one pot"));
+            }
+            state = Some(pots);
         } else if let Some((unparsed_state, next_state)) = line.split_once(" => ") {
+            let pattern = parse_state(i, line, unparsed_state)?;
+            if pattern.len() != 5 {
+                return Err(ParseError::new(
+                    i,
+                    line,
+                    unparsed_state,
+                    "expected five pots",
+                ));
+            }
             match next_state {
                 "#" => {
-                    notes.insert(parse_state(unparsed_state));
+                    notes.insert(pattern);
                 }
                 "." => {}
-                _ => panic!("invalid input"),
+                _ => {
+                    return Err(ParseError::new(i, line, next_state, "expected '#' or '.'"));
+                }
             }
+        } else if !line.is_empty() {
+            return Err(ParseError::line(
+                i,
+                line,
+                "expected an initial state or a note",
+            ));
         }
     }
 
-    (state, notes)
+    let state = state.ok_or_else(|| ParseError::line(0, "", "expected an initial state"))?;
+    Ok((state, notes))
 }
 
 fn pad(state: &mut Vec<bool>, offset: &mut i32) {
@@ -45,8 +82,13 @@ fn pad(state: &mut Vec<bool>, offset: &mut i32) {
     }
 }
 
-pub fn compute(input: &str, generations: i64) -> i64 {
-    let (mut state, notes) = parse(input);
+pub fn compute(input: &str, generations: i64) -> Result<i64, ParseError> {
+    let (state, notes) = parse(input)?;
+    Ok(simulate(&state, &notes, generations))
+}
+
+fn simulate(initial_state: &[bool], notes: &HashSet<Vec<bool>>, generations: i64) -> i64 {
+    let mut state = initial_state.to_vec();
     let mut offset = 0;
     let mut sums = Vec::new();
 
@@ -94,6 +136,24 @@ pub fn compute(input: &str, generations: i64) -> i64 {
     *sums.last().unwrap()
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = (Vec<bool>, HashSet<Vec<bool>>);
+
+    fn parse(&self, input: &str) -> Result<Self::Parsed, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, (state, notes): &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(simulate(state, notes, 20).into())
+    }
+
+    fn part_two(&self, (state, notes): &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(simulate(state, notes, 50_000_000_000).into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,13 +180,33 @@ initial state: #..#.#..##......###...###
 
     #[test]
     fn test() {
-        assert_eq!(compute(EXAMPLE_INPUT, 20), 325);
-        assert_eq!(compute(INPUT, 20), 2542);
+        assert_eq!(compute(EXAMPLE_INPUT, 20).unwrap(), 325);
+        assert_eq!(compute(INPUT, 20).unwrap(), 2542);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(compute(EXAMPLE_INPUT, 50_000_000_000), 50_000_000_501);
-        assert_eq!(compute(INPUT, 50_000_000_000), 2_550_000_000_883);
+        assert_eq!(
+            compute(EXAMPLE_INPUT, 50_000_000_000).unwrap(),
+            50_000_000_501
+        );
+        assert_eq!(compute(INPUT, 50_000_000_000).unwrap(), 2_550_000_000_883);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse("initial state: #..#.x\n\n...## => #\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 21));
+        assert_eq!(err.text, "x");
+
+        let err = parse("initial state: #..#.\n\n...## => ?\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 10));
+
+        assert!(parse("").is_err());
+        assert!(parse("...## => #\n").is_err());
+        let err = parse("initial state: \n").unwrap_err();
+        assert_eq!(err.line, 1);
+        let err = parse("initial state: #\ninitial state: #\n").unwrap_err();
+        assert_eq!(err.line, 2);
     }
 }
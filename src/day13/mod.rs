@@ -1,6 +1,10 @@
 use std::collections::BTreeMap;
 
-use crate::point::{CardinalDirection, Point2D};
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::point::{CardinalDirection, Grid, Point2D, ReadingOrder, TurnOrder};
+use crate::solver::{Error, SolveError, Solver};
+use crate::trace::{Level, event};
 
 type Point = Point2D<usize>;
 
@@ -30,7 +34,7 @@ impl Turn {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Cart {
+pub struct Cart {
     current_direction: CardinalDirection,
     next_turn: Turn,
 }
@@ -51,85 +55,88 @@ impl Cart {
     }
 }
 
-type Carts = BTreeMap<Point, Cart>;
-
-#[allow(dead_code)]
-fn print_map(map: &[Vec<u8>], carts: &Carts) {
-    println!("Map:");
-    for (y, line) in map.iter().enumerate() {
-        for (x, byte) in line.iter().enumerate() {
-            let ch = if let Some(cart) = carts.get(&Point::new(x, y)) {
-                match cart.current_direction {
-                    CardinalDirection::North => '^',
-                    CardinalDirection::South => 'v',
-                    CardinalDirection::East => '>',
-                    CardinalDirection::West => '<',
-                }
-            } else {
-                *byte as char
-            };
-            print!("{}", ch);
+pub type Carts = BTreeMap<Point, Cart>;
+
+/// The track, with a space where there is none.
+pub type Track = Grid<u8>;
+
+fn render_map(map: &Track, carts: &Carts) -> String {
+    let mut s = String::new();
+    for (pos, byte) in map.iter() {
+        s.push(match carts.get(&pos) {
+            Some(cart) => cart.current_direction.arrow(),
+            None => *byte as char,
+        });
+        if pos.x + 1 == map.width() {
+            s.push('\n');
         }
-        println!();
     }
+    s
 }
 
-fn parse(input: &str) -> (Vec<Vec<u8>>, Carts) {
+/// Parses the track and the carts on it.  Lines may be shorter than the
+/// longest one, as when an editor strips the trailing spaces.
+pub fn parse(input: &str) -> Result<(Track, Carts), ParseError> {
     let mut carts = BTreeMap::new();
-    let map: Vec<_> = input
-        .lines()
-        .filter(|line| !line.is_empty())
-        .enumerate()
-        .map(|(y, line)| {
-            line.as_bytes()
-                .iter()
-                .cloned()
-                .enumerate()
-                .map(|(x, byte)| match byte {
-                    b' ' | b'/' | b'\\' | b'-' | b'|' | b'+' => byte,
-                    b'^' | b'v' | b'<' | b'>' => {
-                        let dir = match byte {
-                            b'^' => CardinalDirection::North,
-                            b'v' => CardinalDirection::South,
-                            b'<' => CardinalDirection::West,
-                            b'>' => CardinalDirection::East,
-                            _ => unreachable!(),
-                        };
-                        let replacement_byte = match byte {
-                            b'^' | b'v' => b'|',
-                            b'<' | b'>' => b'-',
-                            _ => unreachable!(),
-                        };
-                        carts.insert(
-                            Point::new(x, y),
-                            Cart {
-                                current_direction: dir,
-                                next_turn: Turn::Left,
-                            },
-                        );
-                        replacement_byte
-                    }
-                    _ => panic!("Invalid byte in map: {}", byte),
-                })
-                .collect::<Vec<u8>>()
-        })
-        .collect();
-    (map, carts)
+    let mut rows: Vec<Vec<u8>> = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let y = rows.len();
+        let row = line
+            .as_bytes()
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(x, byte)| match byte {
+                b' ' | b'/' | b'\\' | b'-' | b'|' | b'+' => Ok(byte),
+                b'^' | b'v' | b'<' | b'>' => {
+                    let dir = CardinalDirection::from_char(byte as char).unwrap();
+                    let replacement_byte = match dir {
+                        CardinalDirection::North | CardinalDirection::South => b'|',
+                        CardinalDirection::East | CardinalDirection::West => b'-',
+                    };
+                    carts.insert(
+                        Point::new(x, y),
+                        Cart {
+                            current_direction: dir,
+                            next_turn: Turn::Left,
+                        },
+                    );
+                    Ok(replacement_byte)
+                }
+                _ => {
+                    let ch = line[x..].chars().next().unwrap();
+                    let text = &line[x..x + ch.len_utf8()];
+                    Err(ParseError::new(i, line, text, "invalid track"))
+                }
+            })
+            .collect::<Result<Vec<u8>, ParseError>>()?;
+        rows.push(row);
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let map = Grid::from_fn(width, rows.len(), |p| {
+        rows[p.y].get(p.x).copied().unwrap_or(b' ')
+    });
+    Ok((map, carts))
 }
 
-fn tick(map: &[Vec<u8>], carts: &Carts) -> (Option<Point>, Carts) {
-    let mut next_carts = carts.clone();
+// Moves every cart once, in reading order, removing carts that collide.
+// Returns the position of the first collision, or an error if a cart runs
+// off the track.
+fn tick(map: &Track, carts: &mut Carts) -> Result<Option<Point>, SolveError> {
     let mut first_collision_at = None;
-    for (from_pos, from_cart) in carts {
-        if next_carts.remove(from_pos).is_none() {
-            // This cart was removed in a prior collision.
-            assert!(first_collision_at.is_some());
-            continue;
-        }
-        let next_pos = from_pos.cardinal_neighbor(from_cart.current_direction);
-        let next_cart = match (map[next_pos.y][next_pos.x], from_cart.current_direction) {
-            (b'|', CardinalDirection::North | CardinalDirection::South) => *from_cart,
-            (b'-', CardinalDirection::West | CardinalDirection::East) => *from_cart,
+    let mut order = TurnOrder::new();
+    while let Some(from_pos) = order.next(|after| ReadingOrder::next_key_after(carts, after)) {
+        let from_cart = carts.remove(&from_pos).unwrap();
+        let next_pos = from_pos
+            .checked_cardinal_neighbor(from_cart.current_direction, map.width(), map.height())
+            .ok_or_else(|| SolveError::new(format!("cart at {} leaves the map", from_pos)))?;
+        let track = map[next_pos];
+        let next_cart = match (track, from_cart.current_direction) {
+            (b'|', CardinalDirection::North | CardinalDirection::South) => from_cart,
+            (b'-', CardinalDirection::West | CardinalDirection::East) => from_cart,
             (b'/', CardinalDirection::North | CardinalDirection::South) => {
                 from_cart.turn(Turn::Right)
             }
@@ -141,58 +148,108 @@ fn tick(map: &[Vec<u8>], carts: &Carts) -> (Option<Point>, Carts) {
                 from_cart.turn(Turn::Right)
             }
             (b'+', _) => from_cart.choose_turn(),
-            invalid => panic!("invalid cart state: {:?}", invalid),
+            (track, dir) => {
+                return Err(SolveError::new(format!(
+                    "cart at {} heading {:?} runs onto {:?}",
+                    from_pos, dir, track as char
+                )));
+            }
         };
-        if next_carts.insert(next_pos, next_cart).is_some() {
+        if carts.insert(next_pos, next_cart).is_some() {
             if first_collision_at.is_none() {
                 first_collision_at = Some(next_pos);
             }
-            next_carts.remove(&next_pos);
+            carts.remove(&next_pos);
+        } else {
+            order.finished_at(next_pos);
         }
     }
 
-    (first_collision_at, next_carts)
+    Ok(first_collision_at)
 }
 
-pub fn compute_part_one(input: &str) -> Point {
-    let (map, mut carts) = parse(input);
+// Carts on a track that keeps them apart never collide, so both parts give
+// up after this many ticks.
+const MAX_TICKS: usize = 100_000;
+
+pub fn compute_part_one(input: &str) -> Result<Point, Error> {
+    let (map, carts) = parse(input)?;
+    Ok(first_collision(&map, carts)?)
+}
+
+fn first_collision(map: &Track, mut carts: Carts) -> Result<Point, SolveError> {
+    if carts.len() < 2 {
+        return Err(SolveError::new(format!(
+            "expected at least two carts, not {}",
+            carts.len()
+        )));
+    }
     let mut ticks = 1;
     loop {
-        // print_map(&map, &carts);
-        let (possible_collision, next_carts) = tick(&map, &carts);
-        if let Some(pos) = possible_collision {
-            return pos;
+        event!(Level::Trace, "tick {}:\n{}", ticks, render_map(map, &carts));
+        if let Some(pos) = tick(map, &mut carts)? {
+            return Ok(pos);
         }
-        carts = next_carts;
         ticks += 1;
-        if ticks > 200 {
-            unreachable!("looped for too many ticks: {}", ticks);
+        if ticks > MAX_TICKS {
+            return Err(SolveError::new(format!(
+                "no collision after {} ticks",
+                MAX_TICKS
+            )));
         }
     }
 }
 
-pub fn compute_part_two(input: &str) -> Point {
-    let (map, mut carts) = parse(input);
-    let mut ticks = 1;
-
-    // Cart count must be odd for the loop to terminate.
-    assert!(carts.len() % 2 == 1);
+pub fn compute_part_two(input: &str) -> Result<Point, Error> {
+    let (map, carts) = parse(input)?;
+    Ok(last_cart(&map, carts)?)
+}
 
+// Carts collide in pairs, so only with an odd number is one left.
+fn last_cart(map: &Track, mut carts: Carts) -> Result<Point, SolveError> {
+    if carts.len().is_multiple_of(2) {
+        return Err(SolveError::new(format!(
+            "expected an odd number of carts, not {}",
+            carts.len()
+        )));
+    }
+    let mut ticks = 1;
     loop {
-        // print_map(&map, &carts);
-        let (_, next_carts) = tick(&map, &carts);
-        carts = next_carts;
+        event!(Level::Trace, "tick {}:\n{}", ticks, render_map(map, &carts));
+        tick(map, &mut carts)?;
         if carts.len() == 1 {
-            return *carts.keys().next().unwrap();
+            return Ok(*carts.keys().next().unwrap());
         }
-        // println!("Step: {}  Cart count: {}", ticks, carts.len());
+        event!(Level::Debug, "tick {}: {} carts", ticks, carts.len());
         ticks += 1;
-        if ticks > 100_000 {
-            unreachable!("looped for too many ticks: {}", ticks);
+        if ticks > MAX_TICKS {
+            return Err(SolveError::new(format!(
+                "{} carts left after {} ticks",
+                carts.len(),
+                MAX_TICKS
+            )));
         }
     }
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = (Track, Carts);
+
+    fn parse(&self, input: &str) -> Result<Self::Parsed, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, (map, carts): &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(first_collision(map, carts.clone())?.into())
+    }
+
+    fn part_two(&self, (map, carts): &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(last_cart(map, carts.clone())?.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,18 +264,66 @@ mod tests {
   \------/   
 "#;
 
+    const EXAMPLE_INPUT_TWO: &str = r#"
+/>-<\  
+|   |  
+| /<+-\
+| | | v
+\>+</ |
+  |   ^
+  \<->/
+"#;
+
     #[test]
     fn test_part_one_example() {
-        assert_eq!(compute_part_one(EXAMPLE_INPUT), Point::new(7, 3));
+        let (map, carts) = parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(render_map(&map, &carts), EXAMPLE_INPUT.trim_start());
+        assert_eq!(compute_part_one(EXAMPLE_INPUT).unwrap(), Point::new(7, 3));
+        // With two carts there is no last one.
+        assert!(compute_part_two(EXAMPLE_INPUT).is_err());
+        assert!(compute_part_one("/---\\\n|   |\n\\---/\n").is_err());
+
+        // Without the trailing spaces the lines are padded back out.
+        let trimmed: String = EXAMPLE_INPUT
+            .lines()
+            .map(|l| l.trim_end().to_string() + "\n")
+            .collect();
+        let (trimmed_map, _) = parse(&trimmed).unwrap();
+        assert_eq!(trimmed_map, map);
+    }
+
+    #[test]
+    fn test_part_two_example() {
+        assert_eq!(
+            compute_part_two(EXAMPLE_INPUT_TWO).unwrap(),
+            Point::new(6, 4)
+        );
     }
 
     #[test]
     fn test_part_one() {
-        assert_eq!(compute_part_one(INPUT), Point::new(115, 138));
+        assert_eq!(compute_part_one(INPUT).unwrap(), Point::new(115, 138));
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(compute_part_two(INPUT), Point::new(0, 98));
+        assert_eq!(compute_part_two(INPUT).unwrap(), Point::new(0, 98));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse("\n/->-\\\n|   x\n\\---/\n").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 5));
+        assert_eq!(err.text, "x");
+    }
+
+    #[test]
+    fn test_solve_error() {
+        // Carts pointing off the track, off the map, or around a loop that
+        // keeps them apart.
+        assert!(compute_part_one("/--->  \\\n|      |\n\\->----/\n").is_err());
+        assert!(compute_part_one("-->\n-<-\n").is_err());
+        assert!(compute_part_two("<--\n").is_err());
+        assert!(compute_part_one("/>-\\\n|  |\n\\-</\n").is_err());
     }
 }
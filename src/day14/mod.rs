@@ -1,3 +1,7 @@
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::solver::{SolveError, Solver};
+
 pub fn compute_part_one(prefix: u32) -> u64 {
     let prefix: usize = prefix.try_into().unwrap();
     let mut v: Vec<u8> = Vec::new();
@@ -33,27 +37,27 @@ pub fn compute_part_one(prefix: u32) -> u64 {
 }
 
 pub fn compute_part_two(needle: u32) -> usize {
-    dbg!(needle);
-    let needle_len = {
-        let mut c: u32 = 0;
-        let mut n = needle;
-        loop {
-            c += 1;
-            n /= 10;
-            if n == 0 {
-                break;
-            }
-        }
-        c
-    };
-    let trailing_modulus = 10_u32.checked_pow(needle_len).unwrap();
+    let digits: Vec<u8> = needle.to_string().bytes().map(|b| b - b'0').collect();
+    recipes_before(&digits)
+}
 
-    let mut v: Vec<u8> = Vec::new();
+/// Returns how many recipes appear before the scores `needle`.  Unlike
+/// `compute_part_two` this keeps leading zeros, which puzzle inputs may have.
+/// Panics if `needle` is empty or longer than nine digits.
+pub fn recipes_before(needle: &[u8]) -> usize {
+    assert!((1..=9).contains(&needle.len()));
+    let mut v: Vec<u8> = vec![3, 7];
+    if let Some(i) = v.windows(needle.len()).position(|w| w == needle) {
+        return i;
+    }
 
-    v.push(3);
-    v.push(7);
-    let mut trailing = 37_u32;
-    trailing %= trailing_modulus;
+    // Compare the last scores as a number, which is faster than comparing
+    // slices; its leading zeros are kept by comparing as many digits as the
+    // needle has, once there are that many scores.
+    let needle_len = needle.len();
+    let target = needle.iter().fold(0, |n, &d| n * 10 + u64::from(d));
+    let trailing_modulus = 10_u64.pow(needle_len as u32);
+    let mut trailing = 37 % trailing_modulus;
 
     let mut a: usize = 0;
     let mut b: usize = 1;
@@ -68,19 +72,53 @@ pub fn compute_part_two(needle: u32) -> usize {
         if sum >= 10 {
             v.push(1);
             trailing = (trailing * 10) % trailing_modulus + 1;
-            if trailing == needle {
-                return v.len() - needle_len as usize;
+            if trailing == target && v.len() >= needle_len {
+                return v.len() - needle_len;
             }
             sum -= 10;
         }
         v.push(sum);
-        trailing = (trailing * 10) % trailing_modulus + sum as u32;
-        if trailing == needle {
-            return v.len() - needle_len as usize;
+        trailing = (trailing * 10) % trailing_modulus + u64::from(sum);
+        if trailing == target && v.len() >= needle_len {
+            return v.len() - needle_len;
         }
     }
 }
 
+/// Parses the puzzle input, a single line of digits.
+pub fn parse(input: &str) -> Result<Vec<u8>, ParseError> {
+    let (i, line) = input
+        .lines()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())
+        .ok_or_else(|| ParseError::line(0, "", "expected digits"))?;
+    let text = line.trim();
+    if text.len() > 9 || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseError::new(i, line, text, "expected up to 9 digits"));
+    }
+    Ok(text.bytes().map(|b| b - b'0').collect())
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<u8>;
+
+    fn parse(&self, input: &str) -> Result<Vec<u8>, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, digits: &Vec<u8>) -> Result<Answer, SolveError> {
+        let prefix = digits.iter().fold(0, |n, &d| n * 10 + d as u32);
+        // The answer is ten digits, which may have leading zeros.
+        Ok(format!("{:010}", compute_part_one(prefix)).into())
+    }
+
+    fn part_two(&self, digits: &Vec<u8>) -> Result<Answer, SolveError> {
+        Ok(recipes_before(digits).into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,7 +128,7 @@ mod tests {
     #[test]
     fn test_part_one_example() {
         assert_eq!(compute_part_one(9), 5158916779);
-        assert_eq!(compute_part_one(5), 0124515891);
+        assert_eq!(compute_part_one(5), 124515891);
         assert_eq!(compute_part_one(18), 9251071085);
         assert_eq!(compute_part_one(2018), 5941429882);
     }
@@ -99,8 +137,28 @@ mod tests {
     fn test_part_one() {
         assert_eq!(compute_part_one(INPUT), 2810862211);
     }
+
     #[test]
     fn test_part_two() {
         assert_eq!(compute_part_two(INPUT), 20227889);
     }
+
+    #[test]
+    fn test_part_two_example() {
+        assert_eq!(compute_part_two(51589), 9);
+        assert_eq!(recipes_before(&[0, 1, 2, 4, 5]), 5);
+        // The scores begin 3, 7, 1, 0, 1, 0, 1, 2, 4, 5.
+        assert_eq!(recipes_before(&[3, 7]), 0);
+        assert_eq!(recipes_before(&[0, 1, 0]), 3);
+        assert_eq!(recipes_before(&[0, 1, 2]), 5);
+        assert_eq!(compute_part_two(92510), 18);
+        assert_eq!(compute_part_two(59414), 2018);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("01245\n"), Ok(vec![0, 1, 2, 4, 5]));
+        let err = parse("\n12a\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
 }
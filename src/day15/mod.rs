@@ -1,4 +1,11 @@
-use std::collections::{BTreeSet, VecDeque};
+use std::collections::BTreeSet;
+
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::point::{self, TurnOrder};
+use crate::search;
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Kind {
@@ -40,16 +47,11 @@ impl std::fmt::Display for Kind {
 struct Creature {
     kind: Kind,
     health: u8,
-    round: u32,
 }
 
 impl Creature {
     fn new(kind: Kind) -> Creature {
-        Creature {
-            kind,
-            health: 200,
-            round: 0,
-        }
+        Creature { kind, health: 200 }
     }
 
     fn as_char(&self) -> char {
@@ -71,14 +73,19 @@ enum Tile {
 }
 
 impl Tile {
-    fn new(ch: char) -> Tile {
+    fn from_char(ch: char) -> Option<Tile> {
         match ch {
-            '#' => Tile::Wall,
-            '.' => Tile::Cavern,
-            _ => panic!("invalid input: '{}'", ch),
+            '#' => Some(Tile::Wall),
+            '.' => Some(Tile::Cavern),
+            'E' | 'G' => Some(Tile::Creature(Creature::new(Kind::new(ch)))),
+            _ => None,
         }
     }
 
+    fn is_creature(&self) -> bool {
+        matches!(self, Tile::Creature(_))
+    }
+
     fn as_char(&self) -> char {
         match self {
             Tile::Cavern => '.',
@@ -91,84 +98,55 @@ impl Tile {
 type Pos = crate::point::Point2D<usize>;
 
 #[derive(Clone)]
-struct Grid {
-    tiles: Vec<Vec<Tile>>,
+pub struct Grid {
+    tiles: point::Grid<Tile>,
     elf_attack_power: u8,
     elf_died: bool,
 }
 
 impl Grid {
-    fn parse(input: &str) -> Grid {
-        let tiles = input
-            .trim()
-            .lines()
-            .map(|line| {
-                line.chars()
-                    .map(|ch| match ch {
-                        '#' | '.' => Tile::new(ch),
-                        'E' | 'G' => Tile::Creature(Creature::new(Kind::new(ch))),
-                        ch => panic!("invalid input: '{}'", ch),
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>();
-        Grid {
-            tiles,
+    // Parses the map, failing unless it has both Elves and Goblins to fight.
+    fn parse(input: &str) -> Result<Grid, ParseError> {
+        let grid = Grid {
+            tiles: point::Grid::parse(input, Tile::from_char)?,
             elf_attack_power: 3,
             elf_died: false,
-        }
-    }
-
-    fn eprint(&self) {
-        for row in self.tiles.iter() {
-            for tile in row.iter() {
-                eprint!("{}", tile.as_char());
+        };
+        for (kind, name) in [(Kind::Elf, "an Elf"), (Kind::Goblin, "a Goblin")] {
+            if !grid.has_any(kind) {
+                return Err(ParseError::line(
+                    0,
+                    "",
+                    format!("expected at least {}", name),
+                ));
             }
-            let mut prefix = "    ";
-            for tile in row.iter() {
-                if let Tile::Creature(c) = tile {
-                    eprint!("{}{}", prefix, c.health);
-                    prefix = " ";
-                }
-            }
-            eprintln!();
         }
+        Ok(grid)
     }
 
-    fn next_creature_position(&self, pos: &Pos) -> Option<Pos> {
-        for (x, tile) in self.tiles[pos.y].iter().enumerate().skip(pos.x + 1) {
-            if let Tile::Creature(_) = tile {
-                return Some(Pos::new(x, pos.y));
-            }
-        }
-        for (y, row) in self.tiles.iter().enumerate().skip(pos.y + 1) {
-            for (x, tile) in row.iter().enumerate() {
-                if let Tile::Creature(_) = tile {
-                    return Some(Pos::new(x, y));
-                }
-            }
-        }
-        None
+    fn tile(&self, pos: &Pos) -> Option<&Tile> {
+        self.tiles.get(*pos)
     }
 
     fn is_cavern(&self, pos: &Pos) -> bool {
-        matches!(self.tiles[pos.y][pos.x], Tile::Cavern)
+        matches!(self.tile(pos), Some(Tile::Cavern))
+    }
+
+    // Returns the neighbors of `pos` on the map, in reading order.
+    fn neighbors(&self, pos: &Pos) -> impl Iterator<Item = Pos> + use<> {
+        pos.cardinal_neighbors_within(self.tiles.width(), self.tiles.height())
     }
 
     fn in_range_of_enemies(&self, enemy_kind: Kind) -> BTreeSet<Pos> {
         let mut in_range = BTreeSet::new();
-        for (y, row) in self.tiles.iter().enumerate() {
-            for (x, tile) in row.iter().enumerate() {
-                match tile {
-                    Tile::Creature(c) if c.kind == enemy_kind => {
-                        let pos = Pos::new(x, y);
-                        for neighbor in pos.cardinal_neighbors() {
-                            if self.is_cavern(&neighbor) {
-                                in_range.insert(neighbor);
-                            }
-                        }
+        for (pos, tile) in self.tiles.iter() {
+            if let Tile::Creature(c) = tile
+                && c.kind == enemy_kind
+            {
+                for neighbor in self.neighbors(&pos) {
+                    if self.is_cavern(&neighbor) {
+                        in_range.insert(neighbor);
                     }
-                    _ => {}
                 }
             }
         }
@@ -178,11 +156,16 @@ impl Grid {
     fn maybe_attack_in_range_target(&mut self, pos: &Pos) -> bool {
         let attacker_kind = self.creature_kind(pos);
         let enemy_kind = attacker_kind.enemy();
-        // eprintln!("   maybe attack {} -> {}", attacker_kind, enemy_kind);
+        event!(
+            Level::Trace,
+            "maybe attack {} -> {}",
+            attacker_kind,
+            enemy_kind
+        );
 
         let mut victim: Option<(Creature, Pos)> = None;
-        for neighbor in pos.cardinal_neighbors() {
-            if let Tile::Creature(neighbor_creature) = self.tiles[neighbor.y][neighbor.x]
+        for neighbor in self.neighbors(pos) {
+            if let Some(&Tile::Creature(neighbor_creature)) = self.tile(&neighbor)
                 && neighbor_creature.kind == enemy_kind
             {
                 match victim {
@@ -196,19 +179,19 @@ impl Grid {
             }
         }
         if let Some((mut victim, victim_pos)) = victim.take() {
-            // eprintln!("   victim: {:?}", victim);
-            // eprintln!("   victim pos: {}", victim_pos);
+            event!(Level::Trace, "victim: {:?}", victim);
+            event!(Level::Trace, "victim pos: {}", victim_pos);
             let attack_power = match attacker_kind {
                 Kind::Goblin => 3,
                 Kind::Elf => self.elf_attack_power,
             };
-            // eprintln!("   attack power: {}", attack_power);
+            event!(Level::Trace, "attack power: {}", attack_power);
             victim.health = victim.health.saturating_sub(attack_power);
-            self.tiles[victim_pos.y][victim_pos.x] = if victim.health > 0 {
-                // eprintln!("   victim health: {}", victim.health);
+            self.tiles[victim_pos] = if victim.health > 0 {
+                event!(Level::Trace, "victim health: {}", victim.health);
                 Tile::Creature(victim)
             } else {
-                // eprintln!("   victim dies!");
+                event!(Level::Trace, "victim dies!");
                 if victim.kind == Kind::Elf {
                     self.elf_died = true;
                 }
@@ -216,24 +199,17 @@ impl Grid {
             };
             true
         } else {
-            // eprintln!("   ...no enemy in range");
+            event!(Level::Trace, "...no enemy in range");
             false
         }
     }
 
     fn get(&self, pos: &Pos) -> &Tile {
-        &self.tiles[pos.y][pos.x]
+        &self.tiles[*pos]
     }
 
     fn get_mut(&mut self, pos: &Pos) -> &mut Tile {
-        &mut self.tiles[pos.y][pos.x]
-    }
-
-    fn get_creature_mut(&mut self, pos: &Pos) -> &mut Creature {
-        match self.get_mut(pos) {
-            Tile::Creature(c) => c,
-            tile => panic!("position {} is not a creature: {:?}", pos, tile),
-        }
+        &mut self.tiles[*pos]
     }
 
     fn creature_kind(&self, pos: &Pos) -> Kind {
@@ -243,33 +219,36 @@ impl Grid {
         }
     }
 
-    fn turn(&mut self, from_pos: &Pos) -> bool {
+    // Returns where the creature ended its turn, or None if it found no
+    // enemies left to fight.
+    fn turn(&mut self, from_pos: &Pos) -> Option<Pos> {
         let enemy = self.creature_kind(from_pos).enemy();
-        // eprintln!("  enemy: {}", enemy);
+        event!(Level::Trace, "enemy: {}", enemy);
         if !self.has_any(enemy) {
-            // eprintln!("   no enemies!");
-            return false;
+            event!(Level::Trace, "no enemies!");
+            return None;
         }
 
         if self.maybe_attack_in_range_target(from_pos) {
-            return true;
+            return Some(*from_pos);
         }
 
         // TODO: use enemy var
         let in_range_of_enemies = self.in_range_of_enemies(enemy);
         if in_range_of_enemies.is_empty() {
-            return true;
+            return Some(*from_pos);
         }
 
         if let Some(to_pos) = self.compute_move_to(in_range_of_enemies, from_pos) {
             self.move_creature(from_pos, &to_pos);
             self.maybe_attack_in_range_target(&to_pos);
+            return Some(to_pos);
         }
-        true
+        Some(*from_pos)
     }
 
     fn move_creature(&mut self, from: &Pos, to: &Pos) {
-        // eprintln!("   move {} -> {}", from, to);
+        event!(Level::Trace, "move {} -> {}", from, to);
         assert_eq!(from.manhattan_distance(*to), 1);
         assert!(matches!(self.get(from), Tile::Creature(_)));
         *self.get_mut(to) = *self.get(from);
@@ -277,67 +256,36 @@ impl Grid {
     }
 
     fn compute_move_to(&self, in_range_of_enemies: BTreeSet<Pos>, from_pos: &Pos) -> Option<Pos> {
-        type Cell = Option<(u32, Pos)>;
-
-        let mut flood: Vec<Vec<Cell>> = self
-            .tiles
-            .iter()
-            .map(|row| row.iter().map(|_| None).collect())
-            .collect();
-
-        let mut queue = VecDeque::new();
-        for pos in in_range_of_enemies.into_iter() {
-            assert!(self.is_cavern(&pos));
-            let wave = Some((0, pos));
-            flood[pos.y][pos.x] = wave;
-            queue.push_back(pos);
-        }
-
-        while let Some(pos) = queue.pop_front() {
-            let wave = match flood[pos.y][pos.x] {
-                Some((dist, pos)) => Some((dist + 1, pos)),
-                _ => panic!("bug: logic error"),
-            };
-            for adj_pos in pos.cardinal_neighbors() {
-                if !self.is_cavern(&adj_pos) {
-                    continue;
-                }
-                let adj = &mut flood[adj_pos.y][adj_pos.x];
-                if adj.is_none() || wave < *adj {
-                    *adj = wave;
-                    queue.push_back(adj_pos);
-                }
-            }
-        }
-
-        from_pos
-            .cardinal_neighbors()
-            .filter_map(|pos| {
-                if let Some((dist, target_pos)) = flood[pos.y][pos.x] {
-                    Some((dist, target_pos, pos))
-                } else {
-                    None
-                }
-            })
+        let open_neighbors = |pos: &Pos| {
+            self.neighbors(pos)
+                .filter(|n| self.is_cavern(n))
+                .collect::<Vec<_>>()
+        };
+
+        // Head for the nearest square in range, first in reading order...
+        let nearest = search::bfs([*from_pos], open_neighbors, |pos| {
+            in_range_of_enemies.contains(pos)
+        });
+        let target = *nearest.goal()?;
+
+        // ...taking the first step in reading order of those on a shortest
+        // path to it.
+        let from_target = search::bfs([target], open_neighbors, |pos| {
+            pos.manhattan_distance(*from_pos) == 1
+        });
+        self.neighbors(from_pos)
+            .filter_map(|pos| Some((from_target.distance(&pos)?, pos)))
             .min()
-            .map(|(_dist, _target_pos, pos)| pos)
+            .map(|(_dist, pos)| pos)
     }
 
-    fn round(&mut self, round: u32) -> bool {
-        let mut pos = Pos::default();
-        while let Some(next_pos) = self.next_creature_position(&pos) {
-            pos = next_pos;
-
-            let creature = self.get_creature_mut(&pos);
-            if creature.round >= round {
-                continue;
-            }
-            creature.round = round;
-
-            let took_action = self.turn(&pos);
-            if !took_action {
+    fn round(&mut self) -> bool {
+        let mut order = TurnOrder::new();
+        while let Some(pos) = order.next(|after| self.tiles.next_after(after, Tile::is_creature)) {
+            let Some(end_pos) = self.turn(&pos) else {
                 return false;
-            }
+            };
+            order.finished_at(end_pos);
 
             // In part two bail early if an Elf dies.
             if self.elf_attack_power > 3 && self.elf_died {
@@ -348,16 +296,13 @@ impl Grid {
     }
 
     fn creature_iter(&self) -> impl Iterator<Item = &Creature> {
-        self.tiles
-            .iter()
-            .flat_map(|row| row.iter())
-            .filter_map(|e| {
-                if let Tile::Creature(c) = e {
-                    Some(c)
-                } else {
-                    None
-                }
-            })
+        self.tiles.values().filter_map(|e| {
+            if let Tile::Creature(c) = e {
+                Some(c)
+            } else {
+                None
+            }
+        })
     }
 
     fn total_hit_points(&self) -> u32 {
@@ -373,38 +318,57 @@ impl Grid {
     }
 
     fn battle(&mut self) -> u32 {
-        let trace = false;
         let mut rounds = 0;
-        if trace {
-            eprintln!("{}", rounds);
-            self.eprint();
-        }
-        while self.round(rounds + 1) {
+        event!(Level::Trace, "initially:\n{}", self);
+        while self.round() {
             rounds += 1;
-            if trace {
-                eprintln!("{}", rounds);
-                self.eprint();
-            }
+            event!(Level::Trace, "after {} rounds:\n{}", rounds, self);
             assert_ne!(rounds, 200);
         }
-        if trace {
-            eprintln!("Finished:");
-            self.eprint();
-        }
+        event!(Level::Trace, "finished:\n{}", self);
         let total_hit_points = self.total_hit_points();
-        dbg!(rounds);
-        dbg!(total_hit_points);
-        dbg!(rounds * total_hit_points);
+        event!(
+            Level::Debug,
+            "{} full rounds, {} total hit points, outcome {}",
+            rounds,
+            total_hit_points,
+            rounds * total_hit_points
+        );
         rounds * total_hit_points
     }
 }
 
-pub fn compute_part_one(input: &str) -> u32 {
-    Grid::parse(input).battle()
+// Renders the map with the hit points of each row's creatures to its right.
+impl std::fmt::Display for Grid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.tiles.rows() {
+            for tile in row.iter() {
+                write!(f, "{}", tile.as_char())?;
+            }
+            let mut prefix = "    ";
+            for tile in row.iter() {
+                if let Tile::Creature(c) = tile {
+                    write!(f, "{}{}", prefix, c.health)?;
+                    prefix = " ";
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
-pub fn compute_part_two(input: &str) -> u32 {
-    let g = Grid::parse(input);
+pub fn compute_part_one(input: &str) -> Result<u32, ParseError> {
+    Ok(Grid::parse(input)?.battle())
+}
+
+pub fn compute_part_two(input: &str) -> Result<u32, ParseError> {
+    Ok(lowest_elf_victory(&Grid::parse(input)?))
+}
+
+// Binary search for the lowest Elf attack power where no Elf dies, returning
+// the outcome of that battle.
+fn lowest_elf_victory(g: &Grid) -> u32 {
     let elf_count = g.count_kind(Kind::Elf);
 
     let mut low: u16 = 4;
@@ -413,13 +377,13 @@ pub fn compute_part_two(input: &str) -> u32 {
 
     while low <= high {
         let power = (low + high) / 2;
-        eprintln!("Elf damage: {}", power);
+        event!(Level::Debug, "trying Elf attack power {}", power);
 
         let mut g = g.clone();
         g.elf_attack_power = power.try_into().unwrap();
         let outcome = g.battle();
         if g.count_kind(Kind::Elf) == elf_count {
-            eprintln!("attack power {} worked!", power);
+            event!(Level::Info, "attack power {} worked", power);
             lowest_outcome = Some(outcome);
             high = power - 1;
         } else {
@@ -430,6 +394,24 @@ pub fn compute_part_two(input: &str) -> u32 {
     lowest_outcome.expect("bug: never found an attack power that caused an Elf victory")
 }
 
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Grid;
+
+    fn parse(&self, input: &str) -> Result<Grid, ParseError> {
+        Grid::parse(input)
+    }
+
+    fn part_one(&self, grid: &Grid) -> Result<Answer, SolveError> {
+        Ok(grid.clone().battle().into())
+    }
+
+    fn part_two(&self, grid: &Grid) -> Result<Answer, SolveError> {
+        Ok(lowest_elf_victory(grid).into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -447,11 +429,11 @@ mod tests {
 "#;
     #[test]
     fn test_part_one_example_first() {
-        assert_eq!(compute_part_one(EXAMPLE_FIRST), 27730);
+        assert_eq!(compute_part_one(EXAMPLE_FIRST).unwrap(), 27730);
     }
     #[test]
     fn test_part_two_example_first() {
-        assert_eq!(compute_part_two(EXAMPLE_FIRST), 4988);
+        assert_eq!(compute_part_two(EXAMPLE_FIRST).unwrap(), 4988);
     }
 
     const EXAMPLE_SECOND: &str = r#"
@@ -465,7 +447,7 @@ mod tests {
 "#;
     #[test]
     fn test_part_one_example_second() {
-        assert_eq!(compute_part_one(EXAMPLE_SECOND), 36334);
+        assert_eq!(compute_part_one(EXAMPLE_SECOND).unwrap(), 36334);
     }
 
     const EXAMPLE_THIRD: &str = r#"
@@ -479,12 +461,12 @@ mod tests {
 "#;
     #[test]
     fn test_part_one_example_third() {
-        assert_eq!(compute_part_one(EXAMPLE_THIRD), 39514);
+        assert_eq!(compute_part_one(EXAMPLE_THIRD).unwrap(), 39514);
     }
 
     #[test]
     fn test_part_two_example_third() {
-        assert_eq!(compute_part_two(EXAMPLE_THIRD), 31284);
+        assert_eq!(compute_part_two(EXAMPLE_THIRD).unwrap(), 31284);
     }
 
     // Combat ends after 35 full rounds
@@ -501,11 +483,11 @@ mod tests {
 "#;
     #[test]
     fn test_part_one_example_fourth() {
-        assert_eq!(compute_part_one(EXAMPLE_FOURTH), 27755);
+        assert_eq!(compute_part_one(EXAMPLE_FOURTH).unwrap(), 27755);
     }
     #[test]
     fn test_part_two_example_fourth() {
-        assert_eq!(compute_part_two(EXAMPLE_FOURTH), 3478);
+        assert_eq!(compute_part_two(EXAMPLE_FOURTH).unwrap(), 3478);
     }
 
     // Combat ends after 54 full rounds
@@ -522,12 +504,12 @@ mod tests {
 "#;
     #[test]
     fn test_part_one_example_fifth() {
-        assert_eq!(compute_part_one(EXAMPLE_FIFTH), 28944);
+        assert_eq!(compute_part_one(EXAMPLE_FIFTH).unwrap(), 28944);
     }
 
     #[test]
     fn test_part_two_example_fifth() {
-        assert_eq!(compute_part_two(EXAMPLE_FIFTH), 6474);
+        assert_eq!(compute_part_two(EXAMPLE_FIFTH).unwrap(), 6474);
     }
 
     // Combat ends after 20 full rounds
@@ -547,21 +529,32 @@ mod tests {
 
     #[test]
     fn test_part_one_example_sixth() {
-        assert_eq!(compute_part_one(EXAMPLE_SIXTH), 18740);
+        assert_eq!(compute_part_one(EXAMPLE_SIXTH).unwrap(), 18740);
     }
 
     #[test]
     fn test_part_two_example_sixth() {
-        assert_eq!(compute_part_two(EXAMPLE_SIXTH), 1140);
+        assert_eq!(compute_part_two(EXAMPLE_SIXTH).unwrap(), 1140);
     }
 
     #[test]
     fn test_part_one() {
-        assert_eq!(compute_part_one(INPUT), 248235);
+        assert_eq!(compute_part_one(INPUT).unwrap(), 248235);
     }
 
     #[test]
     fn test_part_two() {
-        assert_eq!(compute_part_two(INPUT), 46784);
+        assert_eq!(compute_part_two(INPUT).unwrap(), 46784);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = Grid::parse("\n#####\n#E.G#\n#.?.#\n#####\n").err().unwrap();
+        assert_eq!((err.line, err.column), (4, 3));
+        assert_eq!(err.text, "?");
+
+        assert!(Grid::parse("").is_err());
+        assert!(Grid::parse("#####\n#E.E#\n#####\n").is_err());
+        assert!(Grid::parse("#####\n#G..#\n#####\n").is_err());
     }
 }
@@ -0,0 +1,483 @@
+use std::fmt;
+
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::solver::{Error, SolveError, Solver};
+use crate::trace::{Level, event};
+
+/// The sixteen opcodes of the wrist device.  Day 19 runs programs of them
+/// too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Opcode {
+    Addr,
+    Addi,
+    Mulr,
+    Muli,
+    Banr,
+    Bani,
+    Borr,
+    Bori,
+    Setr,
+    Seti,
+    Gtir,
+    Gtri,
+    Gtrr,
+    Eqir,
+    Eqri,
+    Eqrr,
+}
+
+pub const OPCODES: [Opcode; 16] = [
+    Opcode::Addr,
+    Opcode::Addi,
+    Opcode::Mulr,
+    Opcode::Muli,
+    Opcode::Banr,
+    Opcode::Bani,
+    Opcode::Borr,
+    Opcode::Bori,
+    Opcode::Setr,
+    Opcode::Seti,
+    Opcode::Gtir,
+    Opcode::Gtri,
+    Opcode::Gtrr,
+    Opcode::Eqir,
+    Opcode::Eqri,
+    Opcode::Eqrr,
+];
+
+impl Opcode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Opcode::Addr => "addr",
+            Opcode::Addi => "addi",
+            Opcode::Mulr => "mulr",
+            Opcode::Muli => "muli",
+            Opcode::Banr => "banr",
+            Opcode::Bani => "bani",
+            Opcode::Borr => "borr",
+            Opcode::Bori => "bori",
+            Opcode::Setr => "setr",
+            Opcode::Seti => "seti",
+            Opcode::Gtir => "gtir",
+            Opcode::Gtri => "gtri",
+            Opcode::Gtrr => "gtrr",
+            Opcode::Eqir => "eqir",
+            Opcode::Eqri => "eqri",
+            Opcode::Eqrr => "eqrr",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Opcode> {
+        OPCODES.into_iter().find(|op| op.name() == name)
+    }
+
+    /// Returns whether inputs `a` and `b` of an instruction with this opcode
+    /// name registers rather than values.
+    pub fn register_inputs(&self) -> (bool, bool) {
+        match self {
+            Opcode::Addr | Opcode::Mulr | Opcode::Banr | Opcode::Borr => (true, true),
+            Opcode::Gtrr | Opcode::Eqrr => (true, true),
+            Opcode::Addi | Opcode::Muli | Opcode::Bani | Opcode::Bori => (true, false),
+            Opcode::Setr | Opcode::Gtri | Opcode::Eqri => (true, false),
+            Opcode::Gtir | Opcode::Eqir => (false, true),
+            Opcode::Seti => (false, false),
+        }
+    }
+
+    /// Returns the value an instruction with this opcode and inputs `a` and
+    /// `b` computes from `registers`, or None if it reads a register that
+    /// does not exist.
+    pub fn evaluate(&self, registers: &[usize], a: usize, b: usize) -> Option<usize> {
+        let reg = |r: usize| registers.get(r).copied();
+        let value = match self {
+            Opcode::Addr => reg(a)?.wrapping_add(reg(b)?),
+            Opcode::Addi => reg(a)?.wrapping_add(b),
+            Opcode::Mulr => reg(a)?.wrapping_mul(reg(b)?),
+            Opcode::Muli => reg(a)?.wrapping_mul(b),
+            Opcode::Banr => reg(a)? & reg(b)?,
+            Opcode::Bani => reg(a)? & b,
+            Opcode::Borr => reg(a)? | reg(b)?,
+            Opcode::Bori => reg(a)? | b,
+            Opcode::Setr => reg(a)?,
+            Opcode::Seti => a,
+            Opcode::Gtir => (a > reg(b)?).into(),
+            Opcode::Gtri => (reg(a)? > b).into(),
+            Opcode::Gtrr => (reg(a)? > reg(b)?).into(),
+            Opcode::Eqir => (a == reg(b)?).into(),
+            Opcode::Eqri => (reg(a)? == b).into(),
+            Opcode::Eqrr => (reg(a)? == reg(b)?).into(),
+        };
+        Some(value)
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// An instruction whose opcode is `Op`: a number while the numbering is
+/// unknown, later an [`Opcode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instruction<Op> {
+    pub op: Op,
+    pub a: usize,
+    pub b: usize,
+    pub c: usize,
+}
+
+impl Instruction<Opcode> {
+    /// Executes the instruction, returning None and leaving `registers`
+    /// unchanged if it names a register that does not exist.
+    pub fn execute(&self, registers: &mut [usize]) -> Option<()> {
+        let value = self.op.evaluate(registers, self.a, self.b)?;
+        *registers.get_mut(self.c)? = value;
+        Some(())
+    }
+}
+
+impl fmt::Display for Instruction<Opcode> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.op, self.a, self.b, self.c)
+    }
+}
+
+pub type Registers = [usize; 4];
+
+/// The registers before and after running one numbered instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub before: Registers,
+    pub instruction: Instruction<u8>,
+    pub after: Registers,
+}
+
+impl Sample {
+    /// Returns whether the sample's instruction behaves like `op`.
+    pub fn matches(&self, op: Opcode) -> bool {
+        let Instruction { a, b, c, .. } = self.instruction;
+        let mut registers = self.before;
+        let instruction = Instruction { op, a, b, c };
+        instruction.execute(&mut registers).is_some() && registers == self.after
+    }
+
+    pub fn matching_opcodes(&self) -> impl Iterator<Item = Opcode> + '_ {
+        OPCODES.into_iter().filter(|&op| self.matches(op))
+    }
+}
+
+/// The samples and program from the manual, with the opcodes still
+/// numbered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manual {
+    pub samples: Vec<Sample>,
+    pub program: Listing,
+}
+
+// Parse registers of the form:
+// Before: [3, 2, 1, 1]
+fn parse_registers(i: usize, line: &str, prefix: &str) -> Result<Registers, ParseError> {
+    let list = line
+        .strip_prefix(prefix)
+        .map(str::trim)
+        .and_then(|s| s.strip_prefix('['))
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or_else(|| {
+            ParseError::line(i, line, format!("expected {} [a, b, c, d]", prefix.trim()))
+        })?;
+    let values = list
+        .split(',')
+        .map(|s| parse_number(i, line, s.trim()))
+        .collect::<Result<Vec<usize>, _>>()?;
+    values
+        .try_into()
+        .map_err(|_| ParseError::line(i, line, "expected four registers"))
+}
+
+// Parse an instruction of the form:
+// 9 2 1 2
+fn parse_instruction(i: usize, line: &str) -> Result<Instruction<u8>, ParseError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [op, a, b, c] = fields[..] else {
+        return Err(ParseError::line(i, line, "expected: opcode a b c"));
+    };
+    let op: u8 = parse_number(i, line, op)?;
+    if usize::from(op) >= OPCODES.len() {
+        return Err(ParseError::new(
+            i,
+            line,
+            fields[0],
+            "expected opcode 0 to 15",
+        ));
+    }
+    Ok(Instruction {
+        op,
+        a: parse_number(i, line, a)?,
+        b: parse_number(i, line, b)?,
+        c: parse_number(i, line, c)?,
+    })
+}
+
+/// The instructions of a program, each with the index of its line.
+pub type Listing = Vec<(usize, Instruction<u8>)>;
+
+/// Parses the samples and the program that follows them.
+pub fn parse(input: &str) -> Result<Manual, ParseError> {
+    let mut samples = Vec::new();
+    let mut program = Vec::new();
+    let mut lines = input.lines().enumerate();
+    while let Some((i, line)) = lines.next() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with("Before:") {
+            program.push((i, parse_instruction(i, line)?));
+            continue;
+        }
+        if !program.is_empty() {
+            return Err(ParseError::line(i, line, "sample after the program"));
+        }
+        let before = parse_registers(i, line, "Before:")?;
+        let (Some((j, instruction)), Some((k, after))) = (lines.next(), lines.next()) else {
+            return Err(ParseError::line(i, line, "sample ends early"));
+        };
+        samples.push(Sample {
+            before,
+            instruction: parse_instruction(j, instruction)?,
+            after: parse_registers(k, after, "After:")?,
+        });
+    }
+    Ok(Manual { samples, program })
+}
+
+fn decode(opcodes: &[Opcode; 16], instruction: Instruction<u8>) -> Instruction<Opcode> {
+    Instruction {
+        op: opcodes[usize::from(instruction.op)],
+        a: instruction.a,
+        b: instruction.b,
+        c: instruction.c,
+    }
+}
+
+fn count_ambiguous_samples(samples: &[Sample]) -> usize {
+    samples
+        .iter()
+        .filter(|sample| sample.matching_opcodes().count() >= 3)
+        .count()
+}
+
+/// Deduces the opcode of each number from the samples, or returns None if
+/// they leave some number ambiguous.
+pub fn deduce_opcodes(samples: &[Sample]) -> Option<[Opcode; 16]> {
+    // A bitmask of the opcodes each number could still be.
+    let mut candidates = [u16::MAX; 16];
+    for sample in samples {
+        let mask = sample
+            .matching_opcodes()
+            .fold(0, |mask, op| mask | 1 << op as u16);
+        candidates[usize::from(sample.instruction.op)] &= mask;
+    }
+
+    // Repeatedly settle a number with one candidate left and rule that
+    // opcode out for the others.
+    let mut opcodes = [None; 16];
+    while let Some(number) =
+        (0..16).find(|&n| opcodes[n].is_none() && candidates[n].count_ones() == 1)
+    {
+        let op = OPCODES[candidates[number].trailing_zeros() as usize];
+        event!(Level::Debug, "opcode {} is {}", number, op);
+        opcodes[number] = Some(op);
+        for (other, mask) in candidates.iter_mut().enumerate() {
+            if other != number {
+                *mask &= !(1 << op as u16);
+            }
+        }
+    }
+    let mut result = [Opcode::Addr; 16];
+    for (slot, op) in result.iter_mut().zip(opcodes) {
+        *slot = op?;
+    }
+    Some(result)
+}
+
+// Deduces the opcodes from the samples and runs the program, failing if
+// they leave any opcode ambiguous or the program names a register that
+// does not exist.
+fn run_program(manual: &Manual) -> Result<usize, SolveError> {
+    let opcodes = deduce_opcodes(&manual.samples)
+        .ok_or_else(|| SolveError::new("the samples do not determine every opcode"))?;
+    let mut registers = Registers::default();
+    for &(i, instruction) in manual.program.iter() {
+        let instruction = decode(&opcodes, instruction);
+        event!(Level::Trace, "{:?} {}", registers, instruction);
+        instruction.execute(&mut registers).ok_or_else(|| {
+            SolveError::new(format!(
+                "line {}: {} names a register that does not exist",
+                i + 1,
+                instruction
+            ))
+        })?;
+    }
+    Ok(registers[0])
+}
+
+pub fn compute_part_one(input: &str) -> Result<usize, ParseError> {
+    Ok(count_ambiguous_samples(&parse(input)?.samples))
+}
+
+pub fn compute_part_two(input: &str) -> Result<usize, Error> {
+    Ok(run_program(&parse(input)?)?)
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Manual;
+
+    fn parse(&self, input: &str) -> Result<Manual, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, manual: &Manual) -> Result<Answer, SolveError> {
+        Ok(count_ambiguous_samples(&manual.samples).into())
+    }
+
+    fn part_two(&self, manual: &Manual) -> Result<Answer, SolveError> {
+        Ok(run_program(manual)?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("input.txt");
+
+    const EXAMPLE_INPUT: &str = "
+Before: [3, 2, 1, 1]
+9 2 1 2
+After:  [3, 2, 2, 1]
+";
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(compute_part_one(INPUT).unwrap(), 664);
+    }
+
+    #[test]
+    fn test_part_two() {
+        assert_eq!(compute_part_two(INPUT).unwrap(), 247);
+    }
+
+    #[test]
+    fn test_part_one_example() {
+        let manual = parse(EXAMPLE_INPUT).unwrap();
+        assert_eq!(
+            manual.samples[0].matching_opcodes().collect::<Vec<_>>(),
+            vec![Opcode::Addi, Opcode::Mulr, Opcode::Seti]
+        );
+        assert_eq!(compute_part_one(EXAMPLE_INPUT).unwrap(), 1);
+        // One sample is not enough to deduce the opcodes.
+        assert!(compute_part_two(EXAMPLE_INPUT).is_err());
+    }
+
+    // Numbers opcodes in reverse and records samples of each from random
+    // small register values, then runs a program computing whether
+    // (7 + 5) * 3 > 31.
+    fn reversed_manual() -> String {
+        let mut text = String::new();
+        let mut seed = 1_u32;
+        let mut random = || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize % 4
+        };
+        for (number, op) in OPCODES.iter().rev().enumerate() {
+            for _ in 0..8 {
+                let before = [random(), random(), random(), random()];
+                let (a, b, c) = (random(), random(), random());
+                let mut after = before;
+                Instruction { op: *op, a, b, c }
+                    .execute(&mut after)
+                    .unwrap();
+                text += &format!(
+                    "Before: {:?}\n{} {} {} {}\nAfter:  {:?}\n\n",
+                    before, number, a, b, c, after
+                );
+            }
+        }
+        let number = |op: Opcode| 15 - op as u8;
+        text += "\n\n";
+        for (op, a, b, c) in [
+            (Opcode::Seti, 7, 0, 0),
+            (Opcode::Addi, 0, 5, 0),
+            (Opcode::Muli, 0, 3, 0),
+            (Opcode::Seti, 31, 0, 1),
+            (Opcode::Gtir, 35, 0, 2),
+            (Opcode::Gtrr, 0, 1, 0),
+        ] {
+            text += &format!("{} {} {} {}\n", number(op), a, b, c);
+        }
+        text
+    }
+
+    #[test]
+    fn test_deduce_opcodes() {
+        let manual = parse(&reversed_manual()).unwrap();
+        assert_eq!(manual.samples.len(), 128);
+        assert_eq!(manual.program.len(), 6);
+        let opcodes = deduce_opcodes(&manual.samples).unwrap();
+        assert_eq!(opcodes[0], Opcode::Eqrr);
+        assert_eq!(opcodes[15], Opcode::Addr);
+        // 36 > 31.
+        assert_eq!(run_program(&manual), Ok(1));
+        assert_eq!(deduce_opcodes(&manual.samples[..4]), None);
+    }
+
+    #[test]
+    fn test_execute() {
+        let mut registers = [1, 2, 3, 4];
+        let eqri = Instruction {
+            op: Opcode::Eqri,
+            a: 3,
+            b: 4,
+            c: 0,
+        };
+        assert_eq!(eqri.execute(&mut registers), Some(()));
+        assert_eq!(registers, [1, 2, 3, 4]);
+        let addr = Instruction {
+            op: Opcode::Addr,
+            a: 4,
+            b: 0,
+            c: 0,
+        };
+        assert_eq!(addr.execute(&mut registers), None);
+        assert_eq!(Opcode::from_name("bori"), Some(Opcode::Bori));
+        assert_eq!(Opcode::from_name("nop"), None);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse("Before: [3, 2, 1]\n9 2 1 2\nAfter:  [3, 2, 2, 1]\n").unwrap_err();
+        assert_eq!(err.line, 1);
+        let err = parse("Before: [3, 2, 1, 1]\n16 2 1 2\nAfter:  [3, 2, 2, 1]\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        let err = parse("Before: [3, 2, 1, 1]\n9 2 1 2\n").unwrap_err();
+        assert_eq!(err.line, 1);
+        let err = parse("\n1 2 x 4\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 5, "x"));
+    }
+
+    #[test]
+    fn test_solve_error() {
+        assert!(compute_part_two("").is_err());
+
+        // Opcode 0 is eqrr, which has no register 4.
+        let mut manual = reversed_manual();
+        manual += "0 4 0 0\n";
+        let err = run_program(&parse(&manual).unwrap()).unwrap_err();
+        let line = manual.lines().count();
+        assert!(err.message.starts_with(&format!("line {}:", line)));
+    }
+}
@@ -0,0 +1,426 @@
+use crate::answer::Answer;
+use crate::parse::{ParseError, parse_number};
+use crate::point::{Grid, Point2D, Rect};
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
+
+type Pos = Point2D<usize>;
+
+const SPRING_X: usize = 500;
+
+// Parse `<axis>=<n>` or `<axis>=<n>..<m>`, returning the axis and the
+// inclusive range.
+fn parse_coordinate<'a>(
+    i: usize,
+    line: &str,
+    text: &'a str,
+) -> Result<(&'a str, usize, usize), ParseError> {
+    let (axis, range) = text
+        .split_once('=')
+        .filter(|(axis, _)| ["x", "y"].contains(axis))
+        .ok_or_else(|| ParseError::new(i, line, text, "expected x=... or y=..."))?;
+    let (start, end) = range.split_once("..").unwrap_or((range, range));
+    let (start, end) = (parse_number(i, line, start)?, parse_number(i, line, end)?);
+    if start > end {
+        return Err(ParseError::new(
+            i,
+            line,
+            range,
+            "range ends before it starts",
+        ));
+    }
+    Ok((axis, start, end))
+}
+
+// Parse lines of the form:
+// x=495, y=2..7
+// y=7, x=495..501
+pub fn parse(input: &str) -> Result<Vec<Rect<usize>>, ParseError> {
+    let mut veins = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (first, second) = line
+            .split_once(", ")
+            .ok_or_else(|| ParseError::line(i, line, "expected: x=n, y=n..m"))?;
+        let (first_axis, a0, a1) = parse_coordinate(i, line, first)?;
+        let (second_axis, b0, b1) = parse_coordinate(i, line, second)?;
+        let vein = match (first_axis, second_axis) {
+            ("x", "y") => Rect::new(Pos::new(a0, b0), Pos::new(a1, b1)),
+            ("y", "x") => Rect::new(Pos::new(b0, a0), Pos::new(b1, a1)),
+            _ => return Err(ParseError::new(i, line, second, "expected the other axis")),
+        };
+        if vein.min.x == 0 {
+            // Water falling past its left would be at x=-1.
+            let x = if first_axis == "x" { first } else { second };
+            return Err(ParseError::new(i, line, x, "expected x of at least 1"));
+        }
+        veins.push(vein);
+    }
+    if veins.is_empty() {
+        return Err(ParseError::line(
+            0,
+            "",
+            "expected at least one vein of clay",
+        ));
+    }
+    Ok(veins)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Sand,
+    Clay,
+    /// Water that has passed through on its way somewhere else.
+    Flowing,
+    /// Water that has come to rest.
+    Settled,
+}
+
+impl Tile {
+    fn supports_water(&self) -> bool {
+        matches!(self, Tile::Clay | Tile::Settled)
+    }
+}
+
+// Work left while pouring water.
+enum Task {
+    /// Water falling from column `x`, row `top`.
+    Fall { x: usize, top: usize },
+    /// Water falling from `x`, `top` that has landed on row `y` and spreads
+    /// along it.
+    Spread { x: usize, top: usize, y: usize },
+    /// Water spreading along row `y` that pours over whichever of its edges
+    /// are not walls, starting with `edges[next]`.
+    Overflow {
+        x: usize,
+        top: usize,
+        y: usize,
+        edges: [Option<usize>; 2],
+        next: usize,
+        filled_below: bool,
+    },
+}
+
+/// A vertical slice of the ground around the spring.
+#[derive(Clone)]
+pub struct Ground {
+    tiles: Grid<Tile>,
+    /// The x coordinate of the first column.
+    min_x: usize,
+    /// The first row that counts, that of the highest clay.
+    min_y: usize,
+}
+
+impl Ground {
+    /// Creates the ground from the clay veins, leaving a column of sand on
+    /// either side for water to fall down.  Panics if there are no veins or
+    /// one is at x=0, both of which `parse` rejects.
+    pub fn new(veins: &[Rect<usize>]) -> Ground {
+        let bounds = veins
+            .iter()
+            .fold(veins[0], |bounds, vein| bounds.union(vein));
+        let min_x = bounds.min.x.min(SPRING_X) - 1;
+        let max_x = bounds.max.x.max(SPRING_X) + 1;
+        let mut tiles = Grid::new(max_x - min_x + 1, bounds.max.y + 1, Tile::Sand);
+        for vein in veins {
+            for p in vein.points() {
+                tiles[Pos::new(p.x - min_x, p.y)] = Tile::Clay;
+            }
+        }
+        Ground {
+            tiles,
+            min_x,
+            min_y: bounds.min.y,
+        }
+    }
+
+    fn is(&self, x: usize, y: usize, tile: Tile) -> bool {
+        self.tiles.get(Pos::new(x, y)) == Some(&tile)
+    }
+
+    fn supported(&self, x: usize, y: usize) -> bool {
+        self.tiles
+            .get(Pos::new(x, y + 1))
+            .is_some_and(Tile::supports_water)
+    }
+
+    fn set_row(&mut self, y: usize, left: usize, right: usize, tile: Tile) {
+        self.tiles.row_mut(y).unwrap()[left..=right].fill(tile);
+    }
+
+    // Follows water sideways along row `y` from `x` in direction `dx`,
+    // returning where it stops and whether a wall of clay stopped it rather
+    // than falling off an edge.
+    fn spread(&self, x: usize, y: usize, dx: isize) -> (usize, bool) {
+        let mut x = x;
+        while self.supported(x, y) {
+            let next = x.checked_add_signed(dx).unwrap();
+            if self.is(next, y, Tile::Clay) {
+                return (x, true);
+            }
+            x = next;
+        }
+        (x, false)
+    }
+
+    // Pours water down from column `x`, row `top`, filling every basin it
+    // reaches.  The work is kept on a stack rather than recursing for each
+    // overflow, as a large input overflows many basins deep.
+    fn pour(&mut self, x: usize, top: usize) {
+        let mut stack = vec![Task::Fall { x, top }];
+        while let Some(task) = stack.pop() {
+            match task {
+                Task::Fall { x, top } => {
+                    let mut y = top;
+                    while self.is(x, y + 1, Tile::Sand) {
+                        self.tiles[Pos::new(x, y)] = Tile::Flowing;
+                        y += 1;
+                    }
+                    self.tiles[Pos::new(x, y)] = Tile::Flowing;
+                    // Spread, unless it fell off the bottom of the map or
+                    // joined water already flowing.
+                    if self.supported(x, y) {
+                        stack.push(Task::Spread { x, top, y });
+                    }
+                }
+                Task::Spread { x, top, y } => {
+                    let (left, left_wall) = self.spread(x, y, -1);
+                    let (right, right_wall) = self.spread(x, y, 1);
+                    if left_wall && right_wall {
+                        // Fill the row and rise to the one above.
+                        self.set_row(y, left, right, Tile::Settled);
+                        if y > top {
+                            stack.push(Task::Spread { x, top, y: y - 1 });
+                        }
+                        continue;
+                    }
+                    self.set_row(y, left, right, Tile::Flowing);
+                    stack.push(Task::Overflow {
+                        x,
+                        top,
+                        y,
+                        edges: [(!left_wall).then_some(left), (!right_wall).then_some(right)],
+                        next: 0,
+                        filled_below: false,
+                    });
+                }
+                Task::Overflow {
+                    x,
+                    top,
+                    y,
+                    edges,
+                    next,
+                    mut filled_below,
+                } => {
+                    // Water falling from the edge poured last may have filled
+                    // a basin up to this row, so it has to spread again.
+                    if let Some(edge) = next.checked_sub(1).and_then(|i| edges[i]) {
+                        filled_below |= self.supported(edge, y);
+                    }
+                    let pour = (next..edges.len()).find_map(|i| {
+                        edges[i]
+                            .filter(|&edge| self.is(edge, y + 1, Tile::Sand))
+                            .map(|edge| (i, edge))
+                    });
+                    match pour {
+                        Some((i, edge)) => {
+                            stack.push(Task::Overflow {
+                                x,
+                                top,
+                                y,
+                                edges,
+                                next: i + 1,
+                                filled_below,
+                            });
+                            stack.push(Task::Fall {
+                                x: edge,
+                                top: y + 1,
+                            });
+                        }
+                        None if filled_below => stack.push(Task::Spread { x, top, y }),
+                        None => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lets water flow from the spring until nothing changes.
+    pub fn flow(&mut self) {
+        self.pour(SPRING_X - self.min_x, 1);
+        event!(Level::Trace, "ground:\n{}", self.render());
+    }
+
+    // Counts the tiles of type `tile` within the rows of clay.
+    fn count(&self, tile: Tile) -> usize {
+        self.tiles
+            .iter()
+            .filter(|&(p, t)| p.y >= self.min_y && *t == tile)
+            .count()
+    }
+
+    /// Returns the number of tiles water reaches within the rows of clay.
+    pub fn reached(&self) -> usize {
+        self.count(Tile::Flowing) + self.count(Tile::Settled)
+    }
+
+    /// Returns the number of tiles where water comes to rest.
+    pub fn retained(&self) -> usize {
+        self.count(Tile::Settled)
+    }
+
+    /// Renders the ground as in the puzzle, with `+` for the spring, `#`
+    /// for clay, `|` for flowing water and `~` for settled water.
+    pub fn render(&self) -> String {
+        let spring = SPRING_X - self.min_x;
+        let mut s = self.tiles.render(|tile| match tile {
+            Tile::Sand => '.',
+            Tile::Clay => '#',
+            Tile::Flowing => '|',
+            Tile::Settled => '~',
+        });
+        s.replace_range(spring..=spring, "+");
+        s
+    }
+}
+
+fn simulate(veins: &[Rect<usize>]) -> Ground {
+    let mut ground = Ground::new(veins);
+    ground.flow();
+    ground
+}
+
+pub fn compute_part_one(input: &str) -> Result<usize, ParseError> {
+    Ok(simulate(&parse(input)?).reached())
+}
+
+pub fn compute_part_two(input: &str) -> Result<usize, ParseError> {
+    Ok(simulate(&parse(input)?).retained())
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<Rect<usize>>;
+
+    fn parse(&self, input: &str) -> Result<Self::Parsed, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, veins: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(simulate(veins).reached().into())
+    }
+
+    fn part_two(&self, veins: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(simulate(veins).retained().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_INPUT: &str = "
+x=495, y=2..7
+y=7, x=495..501
+x=501, y=3..7
+x=498, y=2..4
+x=506, y=1..2
+x=498, y=10..13
+x=504, y=10..13
+y=13, x=498..504
+";
+
+    #[test]
+    fn test_part_one_example() {
+        assert_eq!(compute_part_one(EXAMPLE_INPUT).unwrap(), 57);
+    }
+
+    #[test]
+    fn test_part_two_example() {
+        assert_eq!(compute_part_two(EXAMPLE_INPUT).unwrap(), 29);
+    }
+
+    #[test]
+    fn test_render() {
+        let ground = simulate(&parse(EXAMPLE_INPUT).unwrap());
+        assert_eq!(
+            ground.render(),
+            "\
+......+.......
+......|.....#.
+.#..#||||...#.
+.#..#~~#|.....
+.#..#~~#|.....
+.#~~~~~#|.....
+.#~~~~~#|.....
+.#######|.....
+........|.....
+...|||||||||..
+...|#~~~~~#|..
+...|#~~~~~#|..
+...|#~~~~~#|..
+...|#######|..
+"
+        );
+    }
+
+    #[test]
+    fn test_nested_basins() {
+        // A small basin inside a larger one: the inner one fills first, then
+        // the outer one fills over it and overflows on both sides.
+        let input = "
+x=495, y=3..8
+x=505, y=3..8
+y=8, x=495..505
+x=499, y=5..6
+x=501, y=5..6
+y=6, x=499..501
+";
+        let ground = simulate(&parse(input).unwrap());
+        assert_eq!(ground.retained(), 9 * 5 - 5);
+        // The overflow falls past both walls to the bottom row; the row it
+        // spreads along is above the highest clay, so does not count.
+        assert_eq!(ground.reached(), ground.retained() + 2 * 6);
+    }
+
+    #[test]
+    fn test_overflow_chain() {
+        // A staircase of shelves, each overflowing onto the next one down
+        // and to the right, poured on a thread with a small stack.
+        let shelves = 1000;
+        let veins: Vec<_> = (0..shelves)
+            .map(|k| Rect::new(Pos::new(499 + k, 2 + 2 * k), Pos::new(501 + k, 2 + 2 * k)))
+            .collect();
+        let ground = std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || simulate(&veins))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(ground.retained(), 0);
+        // The left edge of each shelf and the right edge of the last fall to
+        // the bottom.
+        assert!(ground.is(shelves - 1, 2 * shelves, Tile::Flowing));
+        assert!(ground.is(shelves + 3, 2 * shelves, Tile::Flowing));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse("x=495, y=2..7\nx=495, x=2..7\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 8));
+        let err = parse("z=1, y=2\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        let err = parse("x=495, y=7..2\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (1, 10, "7..2"));
+        assert!(parse("\n").is_err());
+        let err = parse("x=495, y=2..7\ny=7, x=0..3\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 6, "x=0..3"));
+        assert_eq!(
+            parse("y=7, x=1..3\n").unwrap(),
+            vec![Rect::new(Pos::new(1, 7), Pos::new(3, 7))]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::point::Grid;
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Acre {
+    Open,
+    Trees,
+    Lumberyard,
+}
+
+impl Acre {
+    fn from_char(ch: char) -> Option<Acre> {
+        match ch {
+            '.' => Some(Acre::Open),
+            '|' => Some(Acre::Trees),
+            '#' => Some(Acre::Lumberyard),
+            _ => None,
+        }
+    }
+
+    fn as_char(&self) -> char {
+        match self {
+            Acre::Open => '.',
+            Acre::Trees => '|',
+            Acre::Lumberyard => '#',
+        }
+    }
+}
+
+pub type Area = Grid<Acre>;
+
+pub fn parse(input: &str) -> Result<Area, ParseError> {
+    Grid::parse(input, Acre::from_char)
+}
+
+fn step(area: &Area) -> Area {
+    Grid::from_fn(area.width(), area.height(), |pos| {
+        let count = |acre: Acre| {
+            pos.neighbors_within(area.width(), area.height())
+                .filter(|&n| area[n] == acre)
+                .count()
+        };
+        match area[pos] {
+            Acre::Open if count(Acre::Trees) >= 3 => Acre::Trees,
+            Acre::Trees if count(Acre::Lumberyard) >= 3 => Acre::Lumberyard,
+            Acre::Lumberyard if count(Acre::Lumberyard) == 0 || count(Acre::Trees) == 0 => {
+                Acre::Open
+            }
+            acre => acre,
+        }
+    })
+}
+
+/// Returns the area after `minutes`.  Once a state repeats the area cycles
+/// through the same states forever, so the rest is skipped.
+fn after(area: &Area, minutes: usize) -> Area {
+    let mut seen: HashMap<Area, usize> = HashMap::new();
+    let mut history = Vec::new();
+    let mut area = area.clone();
+    for minute in 0..minutes {
+        if let Some(&start) = seen.get(&area) {
+            let period = minute - start;
+            event!(
+                Level::Debug,
+                "minute {} repeats minute {}, a cycle of {}",
+                minute,
+                start,
+                period
+            );
+            return history.swap_remove(start + (minutes - start) % period);
+        }
+        let next = step(&area);
+        seen.insert(area.clone(), minute);
+        history.push(area);
+        area = next;
+    }
+    area
+}
+
+fn resource_value(area: &Area) -> usize {
+    let count = |acre: Acre| area.values().filter(|&&a| a == acre).count();
+    count(Acre::Trees) * count(Acre::Lumberyard)
+}
+
+fn value_after(area: &Area, minutes: usize) -> usize {
+    let area = after(area, minutes);
+    event!(
+        Level::Trace,
+        "after {} minutes:\n{}",
+        minutes,
+        area.render(Acre::as_char)
+    );
+    resource_value(&area)
+}
+
+pub fn compute_part_one(input: &str) -> Result<usize, ParseError> {
+    Ok(value_after(&parse(input)?, 10))
+}
+
+pub fn compute_part_two(input: &str) -> Result<usize, ParseError> {
+    Ok(value_after(&parse(input)?, 1_000_000_000))
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Area;
+
+    fn parse(&self, input: &str) -> Result<Area, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, area: &Area) -> Result<Answer, SolveError> {
+        Ok(value_after(area, 10).into())
+    }
+
+    fn part_two(&self, area: &Area) -> Result<Answer, SolveError> {
+        Ok(value_after(area, 1_000_000_000).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_INPUT: &str = "
+.#.#...|#.
+.....#|##|
+.|..|...#.
+..|#.....#
+#.#|||#|#|
+...#.||...
+.|....|...
+||...#|.#|
+|.||||..|.
+...#.|..|.
+";
+
+    #[test]
+    fn test_part_one_example() {
+        assert_eq!(compute_part_one(EXAMPLE_INPUT).unwrap(), 1147);
+    }
+
+    #[test]
+    fn test_after_example() {
+        let area = after(&parse(EXAMPLE_INPUT).unwrap(), 10);
+        assert_eq!(
+            area.render(Acre::as_char),
+            "\
+.||##.....
+||###.....
+||##......
+|##.....##
+|##.....##
+|##....##|
+||##.####|
+||#####|||
+||||#|||||
+||||||||||
+"
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        // The tree becomes a lumberyard, then with no trees left the
+        // lumberyards are abandoned, and open ground stays open forever.
+        let area = parse("#|.\n##.\n...\n").unwrap();
+        let open = Grid::new(3, 3, Acre::Open);
+        assert_eq!(after(&area, 2), open);
+        assert_eq!(after(&area, 1_000_000_000), open);
+        assert_eq!(after(&area, 0), area);
+
+        // The example stops changing after 18 minutes; skipping ahead must
+        // agree with stepping on either side of that.
+        let area = parse(EXAMPLE_INPUT).unwrap();
+        let mut stepped = area.clone();
+        for minutes in 1..=40 {
+            stepped = step(&stepped);
+            assert_eq!(after(&area, minutes), stepped, "minute {}", minutes);
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse(".#.\n.x.\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+    }
+}
@@ -0,0 +1,339 @@
+use std::ops::ControlFlow;
+
+use crate::answer::Answer;
+use crate::day16::{Instruction, Opcode};
+use crate::parse::{ParseError, parse_number};
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
+
+pub type Registers = [usize; 6];
+
+/// A program whose instruction pointer is bound to register `ip`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub ip: usize,
+    pub instructions: Vec<Instruction<Opcode>>,
+}
+
+// Parse an instruction of the form:
+// seti 5 0 1
+fn parse_instruction(i: usize, line: &str) -> Result<Instruction<Opcode>, ParseError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [name, a, b, c] = fields[..] else {
+        return Err(ParseError::line(i, line, "expected: opcode a b c"));
+    };
+    let op =
+        Opcode::from_name(name).ok_or_else(|| ParseError::new(i, line, name, "unknown opcode"))?;
+    let instruction = Instruction {
+        op,
+        a: parse_number(i, line, a)?,
+        b: parse_number(i, line, b)?,
+        c: parse_number(i, line, c)?,
+    };
+    let (a_register, b_register) = op.register_inputs();
+    for (is_register, register, text) in [
+        (a_register, instruction.a, a),
+        (b_register, instruction.b, b),
+        (true, instruction.c, c),
+    ] {
+        if is_register && register >= Registers::default().len() {
+            return Err(ParseError::new(i, line, text, "expected register 0 to 5"));
+        }
+    }
+    Ok(instruction)
+}
+
+// Parse a program of the form:
+// #ip 0
+// seti 5 0 1
+pub fn parse(input: &str) -> Result<Program, ParseError> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (i, line) = lines
+        .next()
+        .ok_or_else(|| ParseError::line(0, "", "expected #ip N"))?;
+    let register = line
+        .strip_prefix("#ip ")
+        .ok_or_else(|| ParseError::line(i, line, "expected #ip N"))?;
+    let ip = parse_number(i, line, register)?;
+    if ip >= Registers::default().len() {
+        return Err(ParseError::new(
+            i,
+            line,
+            register,
+            "expected register 0 to 5",
+        ));
+    }
+    let instructions = lines
+        .map(|(i, line)| parse_instruction(i, line))
+        .collect::<Result<_, _>>()?;
+    Ok(Program { ip, instructions })
+}
+
+impl Program {
+    /// Runs the program until the instruction pointer leaves it, returning
+    /// None, or until `hook` returns `Break`.  `hook` sees the instruction
+    /// pointer and registers before each instruction.
+    pub fn run<B>(
+        &self,
+        registers: &mut Registers,
+        mut hook: impl FnMut(usize, &Registers) -> ControlFlow<B>,
+    ) -> Option<B> {
+        let mut ip = 0;
+        while let Some(instruction) = self.instructions.get(ip) {
+            registers[self.ip] = ip;
+            if let ControlFlow::Break(b) = hook(ip, registers) {
+                return Some(b);
+            }
+            event!(Level::Trace, "ip={} {:?} {}", ip, registers, instruction);
+            // Checked when parsing.
+            instruction.execute(registers).unwrap();
+            ip = registers[self.ip] + 1;
+        }
+        None
+    }
+
+    // Finds the heart of the puzzle's programs, a pair of nested loops that
+    // adds each x whose product with some y equals a target t to register 0,
+    // then halts:
+    //
+    // seti 1 _ x
+    // seti 1 _ y
+    // mulr x y p
+    // eqrr p t p
+    // addr p ip ip
+    // addi ip 1 ip
+    // addr x 0 0
+    // addi y 1 y
+    // gtrr y t p
+    // addr ip p ip
+    // seti (start + 1) _ ip
+    // addi x 1 x
+    // gtrr x t p
+    // addr p ip ip
+    // seti start _ ip
+    // mulr ip ip ip
+    //
+    // The operands of addr, mulr and eqrr may be either way round.  Returns
+    // the index `start` of the first instruction and the register `t`.
+    fn divisor_sum_loop(&self) -> Option<(usize, usize)> {
+        let ip = self.ip;
+        (0..self.instructions.len()).find_map(|start| {
+            let code = self.instructions.get(start..start + 16)?;
+            let (x, y, p) = (code[0].c, code[1].c, code[2].c);
+            let t = if code[3].a == p { code[3].b } else { code[3].a };
+            let registers = [0, ip, x, y, p, t];
+            if (1..registers.len()).any(|i| registers[..i].contains(&registers[i])) {
+                return None;
+            }
+            let is = |instruction: &Instruction<Opcode>, op, a, b, c| {
+                *instruction == Instruction { op, a, b, c }
+            };
+            let commutes = |instruction: &Instruction<Opcode>, op, a, b, c| {
+                is(instruction, op, a, b, c) || is(instruction, op, b, a, c)
+            };
+            let jumps_to = |instruction: &Instruction<Opcode>, a| {
+                instruction.op == Opcode::Seti && instruction.a == a && instruction.c == ip
+            };
+            let sets_one = |instruction: &Instruction<Opcode>, c| {
+                instruction.op == Opcode::Seti && instruction.a == 1 && instruction.c == c
+            };
+            let halts = (start + 15) * (start + 15) + 1 >= self.instructions.len();
+            let shape = [
+                sets_one(&code[0], x),
+                sets_one(&code[1], y),
+                commutes(&code[2], Opcode::Mulr, x, y, p),
+                commutes(&code[3], Opcode::Eqrr, p, t, p),
+                commutes(&code[4], Opcode::Addr, p, ip, ip),
+                is(&code[5], Opcode::Addi, ip, 1, ip),
+                commutes(&code[6], Opcode::Addr, x, 0, 0),
+                is(&code[7], Opcode::Addi, y, 1, y),
+                is(&code[8], Opcode::Gtrr, y, t, p),
+                commutes(&code[9], Opcode::Addr, p, ip, ip),
+                jumps_to(&code[10], start + 1),
+                is(&code[11], Opcode::Addi, x, 1, x),
+                is(&code[12], Opcode::Gtrr, x, t, p),
+                commutes(&code[13], Opcode::Addr, p, ip, ip),
+                jumps_to(&code[14], start),
+                is(&code[15], Opcode::Mulr, ip, ip, ip) && halts,
+            ];
+            shape.iter().all(|&matches| matches).then_some((start, t))
+        })
+    }
+}
+
+fn divisor_sum(n: usize) -> usize {
+    (1..)
+        .take_while(|d| d * d <= n)
+        .filter(|d| n.is_multiple_of(*d))
+        .map(|d| if d * d == n { d } else { d + n / d })
+        .sum()
+}
+
+/// Returns register 0 once the program halts, starting with `register_0`.
+/// A program ending in the divisor sum loop is run only until it enters the
+/// loop, as running it through takes far too long with register 0 set.
+fn register_0_at_halt(program: &Program, register_0: usize) -> usize {
+    let mut registers = Registers::default();
+    registers[0] = register_0;
+    let target = match program.divisor_sum_loop() {
+        Some((start, target)) => program.run(&mut registers, |ip, registers| {
+            if ip == start {
+                ControlFlow::Break(registers[target])
+            } else {
+                ControlFlow::Continue(())
+            }
+        }),
+        None => program.run(&mut registers, |_, _| ControlFlow::<usize>::Continue(())),
+    };
+    match target {
+        Some(n) => {
+            event!(Level::Debug, "summing the divisors of {}", n);
+            registers[0] + divisor_sum(n)
+        }
+        None => registers[0],
+    }
+}
+
+pub fn compute_part_one(input: &str) -> Result<usize, ParseError> {
+    Ok(register_0_at_halt(&parse(input)?, 0))
+}
+
+pub fn compute_part_two(input: &str) -> Result<usize, ParseError> {
+    Ok(register_0_at_halt(&parse(input)?, 1))
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Program;
+
+    fn parse(&self, input: &str) -> Result<Program, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, program: &Program) -> Result<Answer, SolveError> {
+        Ok(register_0_at_halt(program, 0).into())
+    }
+
+    fn part_two(&self, program: &Program) -> Result<Answer, SolveError> {
+        Ok(register_0_at_halt(program, 1).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_INPUT: &str = "
+#ip 0
+seti 5 0 1
+seti 6 0 2
+addi 0 1 0
+addr 1 2 3
+setr 1 0 0
+seti 8 0 4
+seti 9 0 5
+";
+
+    // A small program shaped like the puzzle inputs: it jumps to a setup
+    // section that puts 10 in register 2, or 30 with register 0 set, then
+    // sums the divisors of register 2 the slow way.
+    const DIVISOR_SUM_INPUT: &str = "
+#ip 4
+addi 4 16 4
+seti 1 8 1
+seti 1 3 5
+mulr 1 5 3
+eqrr 3 2 3
+addr 3 4 4
+addi 4 1 4
+addr 1 0 0
+addi 5 1 5
+gtrr 5 2 3
+addr 4 3 4
+seti 2 7 4
+addi 1 1 1
+gtrr 1 2 3
+addr 3 4 4
+seti 1 6 4
+mulr 4 4 4
+addi 2 10 2
+addr 4 0 4
+seti 0 0 4
+addi 2 20 2
+seti 0 0 0
+seti 0 0 4
+";
+
+    #[test]
+    fn test_part_one_example() {
+        let program = parse(EXAMPLE_INPUT).unwrap();
+        let mut registers = Registers::default();
+        assert_eq!(
+            program.run(&mut registers, |_, _| ControlFlow::<()>::Continue(())),
+            None
+        );
+        assert_eq!(registers, [6, 5, 6, 0, 0, 9]);
+        assert_eq!(compute_part_one(EXAMPLE_INPUT).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_divisor_sum_loop() {
+        let program = parse(DIVISOR_SUM_INPUT).unwrap();
+        assert_eq!(program.divisor_sum_loop(), Some((1, 2)));
+        assert_eq!(compute_part_one(DIVISOR_SUM_INPUT).unwrap(), 1 + 2 + 5 + 10);
+        assert_eq!(compute_part_two(DIVISOR_SUM_INPUT).unwrap(), 72);
+
+        // Running it through agrees with the shortcut.
+        for register_0 in [0, 1] {
+            let mut registers = Registers::default();
+            registers[0] = register_0;
+            program.run(&mut registers, |_, _| ControlFlow::<()>::Continue(()));
+            assert_eq!(registers[0], register_0_at_halt(&program, register_0));
+        }
+    }
+
+    #[test]
+    fn test_not_divisor_sum_loop() {
+        // A product compared with a register, but no loop around it.
+        let input = "#ip 5\nseti 3 0 1\nseti 4 0 2\nmulr 1 2 3\neqrr 3 1 4\naddi 4 7 0\n";
+        let program = parse(input).unwrap();
+        assert_eq!(program.divisor_sum_loop(), None);
+        assert_eq!(compute_part_one(input).unwrap(), 7);
+
+        // The loop adds y rather than x to register 0.
+        let input = DIVISOR_SUM_INPUT.replace("addr 1 0 0", "addr 5 0 0");
+        let program = parse(&input).unwrap();
+        assert_eq!(program.divisor_sum_loop(), None);
+        let mut registers = Registers::default();
+        program.run(&mut registers, |_, _| ControlFlow::<()>::Continue(()));
+        assert_eq!(compute_part_one(&input).unwrap(), registers[0]);
+    }
+
+    #[test]
+    fn test_divisor_sum() {
+        assert_eq!(divisor_sum(1), 1);
+        assert_eq!(divisor_sum(16), 1 + 2 + 4 + 8 + 16);
+        assert_eq!(divisor_sum(30), 72);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(parse("").is_err());
+        let err = parse("#ip 6\n").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 5));
+        let err = parse("#ip 0\nseti 5 0 1\nnope 1 2 3\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (3, 1, "nope"));
+        let err = parse("seti 5 0 1\n").unwrap_err();
+        assert_eq!(err.line, 1);
+        let err = parse("#ip 0\nseti 1 0 9\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 10, "9"));
+        let err = parse("#ip 0\ngtir 1 6 2\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 8, "6"));
+        // Immediate inputs may be any value.
+        assert!(parse("#ip 0\ngtri 1 6 2\n").is_ok());
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::answer::Answer;
+use crate::parse::ParseError;
+use crate::point::{CardinalDirection, Point2D, Rect, SparseGrid};
+use crate::search;
+use crate::solver::{SolveError, Solver};
+use crate::trace::{Level, event};
+
+type Pos = Point2D<i32>;
+
+const DIRECTIONS: [CardinalDirection; 4] = [
+    CardinalDirection::North,
+    CardinalDirection::West,
+    CardinalDirection::East,
+    CardinalDirection::South,
+];
+
+/// One part of a route through the facility.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    Step(CardinalDirection),
+    /// Alternative routes from the same room, any of which may be empty.
+    Branch(Vec<Vec<Route>>),
+}
+
+struct Parser<'a> {
+    i: usize,
+    line: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.line[self.pos..].chars().next()
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        let len = self.peek().map_or(0, char::len_utf8);
+        let text = &self.line[self.pos..self.pos + len];
+        ParseError::new(self.i, self.line, text, message)
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), ParseError> {
+        if self.peek() != Some(ch) {
+            return Err(self.error(&format!("expected '{}'", ch)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // Parses routes up to the `|`, `)` or `$` that ends them.
+    fn sequence(&mut self) -> Result<Vec<Route>, ParseError> {
+        let mut routes = Vec::new();
+        loop {
+            match self.peek() {
+                Some(ch @ ('N' | 'E' | 'S' | 'W')) => {
+                    routes.push(Route::Step(CardinalDirection::from_char(ch).unwrap()));
+                    self.pos += 1;
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let mut alternatives = vec![self.sequence()?];
+                    while self.peek() == Some('|') {
+                        self.pos += 1;
+                        alternatives.push(self.sequence()?);
+                    }
+                    self.expect(')')?;
+                    routes.push(Route::Branch(alternatives));
+                }
+                Some('|' | ')' | '$') => return Ok(routes),
+                _ => return Err(self.error("expected a direction, '(', '|', ')' or '$'")),
+            }
+        }
+    }
+}
+
+// Parse a route regex of the form:
+// ^ENWWW(NEEE|SSE(EE|N))$
+pub fn parse(input: &str) -> Result<Vec<Route>, ParseError> {
+    let (i, line) = input
+        .lines()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())
+        .ok_or_else(|| ParseError::line(0, "", "expected a route"))?;
+    let line = line.trim();
+    let mut parser = Parser { i, line, pos: 0 };
+    parser.expect('^')?;
+    let routes = parser.sequence()?;
+    parser.expect('$')?;
+    if parser.pos < line.len() {
+        return Err(parser.error("unexpected text after '$'"));
+    }
+    Ok(routes)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Room,
+    /// A door, and the way through it from one of the rooms it joins.
+    Door(CardinalDirection),
+}
+
+/// The rooms and doors of the facility.  Rooms are two apart so the doors
+/// between them fit in between, as in the puzzle's maps; the first room is
+/// at the origin.
+pub struct Facility {
+    map: SparseGrid<Tile>,
+}
+
+impl Facility {
+    pub fn new(routes: &[Route]) -> Facility {
+        let mut facility = Facility {
+            map: SparseGrid::new(),
+        };
+        facility.map.insert(Pos::zero(), Tile::Room);
+        facility.follow(routes, BTreeSet::from([Pos::zero()]));
+        facility
+    }
+
+    // Follows `routes` from each of `starts`, opening the doors they pass
+    // through, and returns the rooms where they end.
+    fn follow(&mut self, routes: &[Route], starts: BTreeSet<Pos>) -> BTreeSet<Pos> {
+        let mut rooms = starts;
+        for route in routes {
+            rooms = match route {
+                Route::Step(dir) => rooms
+                    .into_iter()
+                    .map(|room| self.open(room, *dir))
+                    .collect(),
+                Route::Branch(alternatives) => alternatives
+                    .iter()
+                    .flat_map(|alternative| self.follow(alternative, rooms.clone()))
+                    .collect(),
+            };
+        }
+        rooms
+    }
+
+    fn open(&mut self, room: Pos, dir: CardinalDirection) -> Pos {
+        self.map.insert(room.step(dir, 1), Tile::Door(dir));
+        let next = room.step(dir, 2);
+        self.map.insert(next, Tile::Room);
+        next
+    }
+
+    fn neighbors(&self, room: &Pos) -> Vec<Pos> {
+        DIRECTIONS
+            .iter()
+            .filter(|&&dir| self.map.contains(room.step(dir, 1)))
+            .map(|&dir| room.step(dir, 2))
+            .collect()
+    }
+
+    /// Returns the number of doors on the shortest path to each room.
+    pub fn distances(&self) -> HashMap<Pos, usize> {
+        search::bfs([Pos::zero()], |room| self.neighbors(room), |_| false).into_distances()
+    }
+
+    /// Renders the map as in the puzzle, with `#` for walls, `|` and `-` for
+    /// doors, `.` for rooms and `X` for the first room.
+    pub fn render(&self) -> String {
+        let mut s = String::new();
+        let Some(bounds) = self.map.bounds() else {
+            return s;
+        };
+        let one = Pos::new(1, 1);
+        let outer = Rect::new(bounds.min - one, bounds.max + one);
+        for p in outer.points() {
+            s.push(match self.map.get(p) {
+                _ if p == Pos::zero() => 'X',
+                Some(Tile::Room) => '.',
+                Some(Tile::Door(CardinalDirection::East | CardinalDirection::West)) => '|',
+                Some(Tile::Door(CardinalDirection::North | CardinalDirection::South)) => '-',
+                None => '#',
+            });
+            if p.x == outer.max.x {
+                s.push('\n');
+            }
+        }
+        s
+    }
+}
+
+fn distances(routes: &[Route]) -> HashMap<Pos, usize> {
+    let facility = Facility::new(routes);
+    event!(Level::Trace, "facility:\n{}", facility.render());
+    facility.distances()
+}
+
+fn furthest_room(routes: &[Route]) -> usize {
+    distances(routes).into_values().max().unwrap_or(0)
+}
+
+fn rooms_at_least(routes: &[Route], doors: usize) -> usize {
+    distances(routes)
+        .into_values()
+        .filter(|&d| d >= doors)
+        .count()
+}
+
+pub fn compute_part_one(input: &str) -> Result<usize, ParseError> {
+    Ok(furthest_room(&parse(input)?))
+}
+
+pub fn compute_part_two(input: &str) -> Result<usize, ParseError> {
+    Ok(rooms_at_least(&parse(input)?, 1000))
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<Route>;
+
+    fn parse(&self, input: &str) -> Result<Vec<Route>, ParseError> {
+        parse(input)
+    }
+
+    fn part_one(&self, routes: &Vec<Route>) -> Result<Answer, SolveError> {
+        Ok(furthest_room(routes).into())
+    }
+
+    fn part_two(&self, routes: &Vec<Route>) -> Result<Answer, SolveError> {
+        Ok(rooms_at_least(routes, 1000).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one_examples() {
+        for (input, doors) in [
+            ("^WNE$", 3),
+            ("^ENWWW(NEEE|SSE(EE|N))$", 10),
+            ("^ENNWSWW(NEWS|)SSSEEN(WNSE|)EE(SWEN|)NNN$", 18),
+            ("^ESSWWN(E|NNENN(EESS(WNSE|)SSS|WWWSSSSE(SW|NNNE)))$", 23),
+            (
+                "^WSSEESWWWNW(S|NENNEEEENN(ESSSSW(NWSW|SSEN)|WSWWN(E|WWS(E|SS))))$",
+                31,
+            ),
+        ] {
+            assert_eq!(compute_part_one(input).unwrap(), doors, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_rooms_at_least() {
+        let routes = parse("^ENWWW(NEEE|SSE(EE|N))$").unwrap();
+        assert_eq!(rooms_at_least(&routes, 0), 16);
+        assert_eq!(rooms_at_least(&routes, 9), 4);
+        assert_eq!(rooms_at_least(&routes, 11), 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let render = |input| Facility::new(&parse(input).unwrap()).render();
+        assert_eq!(
+            render("^WNE$"),
+            "\
+#####
+#.|.#
+#-###
+#.|X#
+#####
+"
+        );
+        assert_eq!(
+            render("^ENWWW(NEEE|SSE(EE|N))$"),
+            "\
+#########
+#.|.|.|.#
+#-#######
+#.|.|.|.#
+#-#####-#
+#.#.#X|.#
+#-#-#####
+#.|.|.|.#
+#########
+"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("^N(E|)$").unwrap(),
+            vec![
+                Route::Step(CardinalDirection::North),
+                Route::Branch(vec![vec![Route::Step(CardinalDirection::East)], vec![]]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse("^NE(W|S$").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (1, 8, "$"));
+        let err = parse("\nNEWS$\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        let err = parse("^NEXS$").unwrap_err();
+        assert_eq!((err.column, err.text.as_str()), (4, "X"));
+        let err = parse("^NE)$").unwrap_err();
+        assert_eq!(err.column, 4);
+        let err = parse("^NE").unwrap_err();
+        assert_eq!((err.column, err.text.as_str()), (4, ""));
+        assert!(parse("^N$E").is_err());
+    }
+}
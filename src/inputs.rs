@@ -0,0 +1,143 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// The name of the puzzle inputs checked in alongside each day.
+pub const BUILTIN: &str = "builtin";
+
+/// Returns the checked in puzzle input for `day`.  Days whose puzzles take
+/// numbers rather than a file use the text from the puzzle page.
+pub fn builtin(day: u8) -> Option<&'static str> {
+    let input = match day {
+        1 => include_str!("day01/input.txt"),
+        2 => include_str!("day02/input.txt"),
+        3 => include_str!("day03/input.txt"),
+        4 => include_str!("day04/input.txt"),
+        5 => include_str!("day05/input.txt"),
+        6 => include_str!("day06/input.txt"),
+        7 => include_str!("day07/input.txt"),
+        8 => include_str!("day08/input.txt"),
+        9 => "493 players; last marble is worth 71863 points\n",
+        10 => include_str!("day10/input.txt"),
+        11 => "9424\n",
+        12 => include_str!("day12/input.txt"),
+        13 => include_str!("day13/input.txt"),
+        14 => "509671\n",
+        15 => include_str!("day15/input.txt"),
+        16 => include_str!("day16/input.txt"),
+        _ => return None,
+    };
+    Some(input)
+}
+
+/// Puzzle inputs stored at runtime as `<dir>/<name>/dayNN.txt`, one
+/// directory per named input set, e.g. one per account.  The name
+/// [`BUILTIN`] always refers to the checked in inputs.
+///
+/// Every day's file holds the text of its puzzle input, including days 9,
+/// 11 and 14 whose puzzles give numbers rather than a file:
+///
+/// ```text
+/// day09.txt: 493 players; last marble is worth 71863 points
+/// day11.txt: 9424
+/// day14.txt: 509671
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputStore {
+    dir: PathBuf,
+}
+
+impl InputStore {
+    pub fn new(dir: impl Into<PathBuf>) -> InputStore {
+        InputStore { dir: dir.into() }
+    }
+
+    pub fn path(&self, name: &str, day: u8) -> PathBuf {
+        self.dir.join(name).join(format!("day{:02}.txt", day))
+    }
+
+    /// Loads the input for `day` from the set `name`.
+    pub fn load(&self, name: &str, day: u8) -> io::Result<String> {
+        if name == BUILTIN {
+            return builtin(day).map(str::to_string).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no builtin input for day {}", day),
+                )
+            });
+        }
+        let path = self.path(name, day);
+        fs::read_to_string(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("reading {}: {}", path.display(), e)))
+    }
+
+    /// Returns the names of the input sets in sorted order, starting with
+    /// [`BUILTIN`].  A missing directory holds no sets besides the builtin
+    /// one.
+    pub fn names(&self) -> io::Result<Vec<String>> {
+        let mut names = Vec::new();
+        match fs::read_dir(&self.dir) {
+            Ok(entries) => {
+                for entry in entries {
+                    let entry = entry?;
+                    if !entry.file_type()?.is_dir() {
+                        continue;
+                    }
+                    if let Some(name) = entry.file_name().to_str()
+                        && name != BUILTIN
+                    {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        names.sort();
+        names.insert(0, BUILTIN.to_string());
+        Ok(names)
+    }
+
+    /// Returns the days among 1 to 25 that the set `name` has an input for.
+    pub fn days(&self, name: &str) -> Vec<u8> {
+        (1..=25)
+            .filter(|&day| {
+                if name == BUILTIN {
+                    builtin(day).is_some()
+                } else {
+                    self.path(name, day).is_file()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_store() {
+        let dir = std::env::temp_dir().join(format!("aoc2018-inputs-{}", std::process::id()));
+        fs::create_dir_all(dir.join("alice")).unwrap();
+        fs::create_dir_all(dir.join("bob")).unwrap();
+        fs::write(dir.join("alice/day11.txt"), "18\n").unwrap();
+        fs::write(dir.join("alice/day14.txt"), "01245\n").unwrap();
+
+        let store = InputStore::new(&dir);
+        assert_eq!(store.names().unwrap(), vec!["builtin", "alice", "bob"]);
+        assert_eq!(store.days("alice"), vec![11, 14]);
+        assert_eq!(store.days("bob"), Vec::<u8>::new());
+        assert_eq!(store.days(BUILTIN).len(), 16);
+        assert_eq!(store.load("alice", 11).unwrap(), "18\n");
+        assert_eq!(store.load(BUILTIN, 11).unwrap(), "9424\n");
+        let err = store.load("bob", 11).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(store.load(BUILTIN, 25).is_err());
+
+        let missing = InputStore::new(dir.join("missing"));
+        assert_eq!(missing.names().unwrap(), vec!["builtin"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
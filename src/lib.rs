@@ -1,10 +1,21 @@
+pub mod answer;
+pub mod answers;
+pub mod bench;
+pub mod inputs;
+pub mod parse;
 pub mod point;
+pub mod registry;
+pub mod search;
+pub mod solver;
+pub mod trace;
+pub mod union_find;
+pub mod verify;
 
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
 pub mod day06;
 pub mod day07;
 pub mod day08;
@@ -15,3 +26,8 @@ pub mod day12;
 pub mod day13;
 pub mod day14;
 pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
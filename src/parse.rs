@@ -0,0 +1,106 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// An error in puzzle input, located by 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    /// The offending text.
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    /// Creates an error for `text` on the line at `line_index` (0-based, as
+    /// from `lines().enumerate()`).  When `text` is a slice of `line` the
+    /// column is where it starts, otherwise the column is 1.
+    pub fn new(
+        line_index: usize,
+        line: &str,
+        text: &str,
+        message: impl Into<String>,
+    ) -> ParseError {
+        let line_start = line.as_ptr() as usize;
+        let text_start = text.as_ptr() as usize;
+        let column =
+            if line_start <= text_start && text_start + text.len() <= line_start + line.len() {
+                line[..text_start - line_start].chars().count() + 1
+            } else {
+                1
+            };
+        ParseError {
+            line: line_index + 1,
+            column,
+            text: text.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error for the whole line at `line_index`.
+    pub fn line(line_index: usize, line: &str, message: impl Into<String>) -> ParseError {
+        ParseError::new(line_index, line, line, message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}: {:?}",
+            self.line, self.column, self.message, self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses `text`, a slice of the line at `line_index`, as a number.
+pub fn parse_number<T: FromStr>(
+    line_index: usize,
+    line: &str,
+    text: &str,
+) -> Result<T, ParseError> {
+    text.parse()
+        .map_err(|_| ParseError::new(line_index, line, text, "invalid number"))
+}
+
+/// Parses input consisting of a single number, such as day 11's grid serial
+/// number.
+pub fn parse_single_number<T: FromStr>(input: &str) -> Result<T, ParseError> {
+    let (line_index, line) = input
+        .lines()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())
+        .ok_or_else(|| ParseError::line(0, "", "expected a number"))?;
+    parse_number(line_index, line, line.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column() {
+        let line = "#1 @ 3,x: 4x4";
+        let err = parse_number::<usize>(6, line, &line[7..8]).unwrap_err();
+        assert_eq!(err.line, 7);
+        assert_eq!(err.column, 8);
+        assert_eq!(err.text, "x");
+        assert_eq!(err.to_string(), r#"line 7, column 8: invalid number: "x""#);
+    }
+
+    #[test]
+    fn test_text_outside_line() {
+        let err = ParseError::new(0, "abc", "xyz", "bad");
+        assert_eq!(err.column, 1);
+    }
+
+    #[test]
+    fn test_parse_single_number() {
+        assert_eq!(parse_single_number::<u32>("\n 509671 \n"), Ok(509671));
+        let err = parse_single_number::<u32>("\nabc\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert!(parse_single_number::<u32>("").is_err());
+    }
+}
@@ -1,9 +1,16 @@
-use std::cmp::Ord;
+use std::cell::Cell;
+use std::cmp::{Ord, Ordering};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::hash::Hash;
 use std::ops::Add;
+use std::ops::Bound::{Excluded, Unbounded};
 use std::ops::Sub;
+use std::ops::{AddAssign, Index, IndexMut, Mul, Neg, SubAssign};
 
-use num::One;
+use num::{CheckedAdd, CheckedSub, One, Signed, Zero};
+
+use crate::parse::ParseError;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum CardinalDirection {
@@ -14,6 +21,28 @@ pub enum CardinalDirection {
 }
 
 impl CardinalDirection {
+    /// Parses an arrow `^`, `v`, `<` or `>`, or a compass letter `N`, `S`,
+    /// `E` or `W`.
+    pub fn from_char(ch: char) -> Option<CardinalDirection> {
+        match ch {
+            '^' | 'N' => Some(CardinalDirection::North),
+            'v' | 'S' => Some(CardinalDirection::South),
+            '>' | 'E' => Some(CardinalDirection::East),
+            '<' | 'W' => Some(CardinalDirection::West),
+            _ => None,
+        }
+    }
+
+    /// Returns the arrow pointing this way, as drawn on puzzle maps.
+    pub fn arrow(&self) -> char {
+        match *self {
+            CardinalDirection::North => '^',
+            CardinalDirection::South => 'v',
+            CardinalDirection::East => '>',
+            CardinalDirection::West => '<',
+        }
+    }
+
     pub fn negate(&self) -> Self {
         match *self {
             CardinalDirection::North => CardinalDirection::South,
@@ -49,6 +78,119 @@ pub const CARDINAL_DIRECTIONS: [CardinalDirection; 4] = [
     CardinalDirection::West,
 ];
 
+/// One of the eight directions to a neighbor on a grid, with north
+/// towards smaller y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction8 {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+/// The eight directions clockwise from north.
+pub const DIRECTIONS_8: [Direction8; 8] = [
+    Direction8::North,
+    Direction8::NorthEast,
+    Direction8::East,
+    Direction8::SouthEast,
+    Direction8::South,
+    Direction8::SouthWest,
+    Direction8::West,
+    Direction8::NorthWest,
+];
+
+impl Direction8 {
+    // Returns the direction `eighths` eighths of a turn clockwise.
+    fn turned(&self, eighths: usize) -> Direction8 {
+        DIRECTIONS_8[(*self as usize + eighths) % 8]
+    }
+
+    /// Turns 45 degrees clockwise.
+    pub fn right45(&self) -> Direction8 {
+        self.turned(1)
+    }
+
+    /// Turns 45 degrees counterclockwise.
+    pub fn left45(&self) -> Direction8 {
+        self.turned(7)
+    }
+
+    /// Turns 90 degrees clockwise.
+    pub fn right(&self) -> Direction8 {
+        self.turned(2)
+    }
+
+    /// Turns 90 degrees counterclockwise.
+    pub fn left(&self) -> Direction8 {
+        self.turned(6)
+    }
+
+    pub fn negate(&self) -> Direction8 {
+        self.turned(4)
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        (*self as usize) % 2 == 1
+    }
+
+    /// Returns the x and y steps, each -1, 0 or 1.
+    pub fn unit(&self) -> (i8, i8) {
+        match *self {
+            Direction8::North => (0, -1),
+            Direction8::NorthEast => (1, -1),
+            Direction8::East => (1, 0),
+            Direction8::SouthEast => (1, 1),
+            Direction8::South => (0, 1),
+            Direction8::SouthWest => (-1, 1),
+            Direction8::West => (-1, 0),
+            Direction8::NorthWest => (-1, -1),
+        }
+    }
+
+    /// Returns the offset to the neighbor in this direction.
+    pub fn offset<T: From<i8>>(&self) -> Point2D<T> {
+        let (x, y) = self.unit();
+        Point2D {
+            x: x.into(),
+            y: y.into(),
+        }
+    }
+
+    /// Returns the direction of a unit `offset`, or None if `offset` is not
+    /// to a neighbor.
+    pub fn from_offset<T: TryInto<i8>>(offset: Point2D<T>) -> Option<Direction8> {
+        let unit = (offset.x.try_into().ok()?, offset.y.try_into().ok()?);
+        DIRECTIONS_8.into_iter().find(|dir| dir.unit() == unit)
+    }
+
+    /// Returns this direction if it is north, south, east or west.
+    pub fn cardinal(&self) -> Option<CardinalDirection> {
+        match *self {
+            Direction8::North => Some(CardinalDirection::North),
+            Direction8::South => Some(CardinalDirection::South),
+            Direction8::East => Some(CardinalDirection::East),
+            Direction8::West => Some(CardinalDirection::West),
+            _ => None,
+        }
+    }
+}
+
+impl From<CardinalDirection> for Direction8 {
+    fn from(dir: CardinalDirection) -> Direction8 {
+        match dir {
+            CardinalDirection::North => Direction8::North,
+            CardinalDirection::South => Direction8::South,
+            CardinalDirection::East => Direction8::East,
+            CardinalDirection::West => Direction8::West,
+        }
+    }
+}
+
 #[derive(Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Point2D<T> {
     pub y: T,
@@ -82,21 +224,175 @@ impl<T: Add<Output = T> + Sub<Output = T> + Ord + Copy + TryFrom<usize> + TryInt
         abs_difference(self.x, other.x) + abs_difference(self.y, other.y)
     }
 
+    /// Returns the eight neighbors, skipping any whose coordinates would
+    /// overflow, as for unsigned points in row or column zero.
     pub fn neighbors(&self) -> NeighborsIterator<T> {
         NeighborsIterator::new(*self)
     }
 
+    /// Returns the four cardinal neighbors in reading order, skipping any
+    /// whose coordinates would overflow.
     pub fn cardinal_neighbors(&self) -> CardinalNeighborsIterator<T> {
         CardinalNeighborsIterator::new(*self)
     }
 
     pub fn cardinal_neighbor(&self, dir: CardinalDirection) -> Self {
-        let one = T::one();
-        match dir {
-            CardinalDirection::North => Self::new(self.x, self.y - one),
-            CardinalDirection::South => Self::new(self.x, self.y + one),
-            CardinalDirection::East => Self::new(self.x + one, self.y),
-            CardinalDirection::West => Self::new(self.x - one, self.y),
+        self.step(dir, T::one())
+    }
+
+    /// Returns the point `n` steps away in direction `dir`, where a
+    /// diagonal step changes both x and y by one.
+    pub fn step(&self, dir: impl Into<Direction8>, n: T) -> Self {
+        let (dx, dy) = dir.into().unit();
+        Self::new(shift(self.x, dx, n), shift(self.y, dy, n))
+    }
+}
+
+impl<
+    T: Add<Output = T> + Sub<Output = T> + Ord + Copy + TryFrom<usize> + TryInto<usize> + One + Zero,
+> Point2D<T>
+{
+    /// Returns the neighbor in direction `dir` if it lies within a grid of
+    /// `width` by `height` points with a corner at the origin.  Never
+    /// underflows, so it is safe for unsigned points in row or column zero.
+    pub fn checked_cardinal_neighbor(
+        &self,
+        dir: CardinalDirection,
+        width: T,
+        height: T,
+    ) -> Option<Self> {
+        let (dx, dy) = Direction8::from(dir).unit();
+        self.checked_offset(dx, dy, width, height)
+    }
+
+    /// Like [`Point2D::cardinal_neighbors`], in the same order, but skips
+    /// neighbors outside a grid of `width` by `height` points with a corner
+    /// at the origin.
+    pub fn cardinal_neighbors_within(
+        &self,
+        width: T,
+        height: T,
+    ) -> impl Iterator<Item = Self> + use<T> {
+        let point = *self;
+        CARDINAL_NEIGHBOR_ORDER.into_iter().filter_map(move |dir| {
+            let (dx, dy) = Direction8::from(dir).unit();
+            point.checked_offset(dx, dy, width, height)
+        })
+    }
+
+    /// Like [`Point2D::neighbors`], in the same order, but skips neighbors
+    /// outside a grid of `width` by `height` points with a corner at the
+    /// origin.
+    pub fn neighbors_within(&self, width: T, height: T) -> impl Iterator<Item = Self> + use<T> {
+        let point = *self;
+        NEIGHBOR_ORDER.into_iter().filter_map(move |dir| {
+            let (dx, dy) = dir.unit();
+            point.checked_offset(dx, dy, width, height)
+        })
+    }
+
+    fn checked_offset(&self, dx: i8, dy: i8, width: T, height: T) -> Option<Self> {
+        Some(Self::new(
+            checked_step(self.x, dx, width)?,
+            checked_step(self.y, dy, height)?,
+        ))
+    }
+}
+
+// Moves `value` one unit in the direction of the sign of `delta`, returning
+// None unless both `value` and the result are in `0..limit`.
+fn checked_step<T: Add<Output = T> + Sub<Output = T> + Ord + Copy + One + Zero>(
+    value: T,
+    delta: i8,
+    limit: T,
+) -> Option<T> {
+    if value < T::zero() || value >= limit {
+        return None;
+    }
+    match delta.signum() {
+        -1 if value > T::zero() => Some(value - T::one()),
+        0 => Some(value),
+        1 if value + T::one() < limit => Some(value + T::one()),
+        _ => None,
+    }
+}
+
+/// Metrics and symmetries.  With y growing downwards, as on puzzle maps,
+/// turning right is clockwise on the screen.  The methods work for unsigned
+/// points as long as the result, and the coordinate sums along the way,
+/// are not negative.
+impl<T: Add<Output = T> + Sub<Output = T> + Ord + Copy + Zero> Point2D<T> {
+    /// Returns the number of king moves between the points.
+    pub fn chebyshev_distance(&self, other: Self) -> T {
+        abs_difference(self.x, other.x).max(abs_difference(self.y, other.y))
+    }
+
+    pub fn squared_euclidean_distance(&self, other: Self) -> T
+    where
+        T: Mul<Output = T>,
+    {
+        let dx = abs_difference(self.x, other.x);
+        let dy = abs_difference(self.y, other.y);
+        dx * dx + dy * dy
+    }
+
+    /// Rotates 90 degrees clockwise around the origin.
+    pub fn rotate_right(&self) -> Self {
+        self.rotate_right_around(Point2D::zero())
+    }
+
+    /// Rotates 90 degrees counterclockwise around the origin.
+    pub fn rotate_left(&self) -> Self {
+        self.rotate_left_around(Point2D::zero())
+    }
+
+    /// Rotates 90 degrees clockwise around `center`.
+    pub fn rotate_right_around(&self, center: Self) -> Self {
+        Point2D {
+            x: center.x + center.y - self.y,
+            y: center.y + self.x - center.x,
+        }
+    }
+
+    /// Rotates 90 degrees counterclockwise around `center`.
+    pub fn rotate_left_around(&self, center: Self) -> Self {
+        Point2D {
+            x: center.x + self.y - center.y,
+            y: center.y + center.x - self.x,
+        }
+    }
+
+    /// Mirrors across the vertical line through x = `axis`.
+    pub fn reflect_x(&self, axis: T) -> Self {
+        Point2D {
+            x: axis + axis - self.x,
+            y: self.y,
+        }
+    }
+
+    /// Mirrors across the horizontal line through y = `axis`.
+    pub fn reflect_y(&self, axis: T) -> Self {
+        Point2D {
+            x: self.x,
+            y: axis + axis - self.y,
+        }
+    }
+
+    /// Mirrors across the diagonal x = y, swapping the coordinates.
+    pub fn transpose(&self) -> Self {
+        Point2D {
+            x: self.y,
+            y: self.x,
+        }
+    }
+}
+
+impl<T: Zero> Point2D<T> {
+    /// Returns the origin.
+    pub fn zero() -> Self {
+        Point2D {
+            x: T::zero(),
+            y: T::zero(),
         }
     }
 }
@@ -124,6 +420,43 @@ impl<T: Sub<Output = T>> Sub for Point2D<T> {
     }
 }
 
+impl<T: AddAssign> AddAssign for Point2D<T> {
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+impl<T: SubAssign> SubAssign for Point2D<T> {
+    fn sub_assign(&mut self, other: Self) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
+/// Scales both coordinates by a scalar.
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point2D<T> {
+    type Output = Self;
+
+    fn mul(self, scalar: T) -> Self::Output {
+        Self {
+            x: self.x * scalar,
+            y: self.y * scalar,
+        }
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point2D<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
 impl<T: fmt::Display> fmt::Display for Point2D<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({}, {})", self.x, self.y)
@@ -138,7 +471,7 @@ impl<T: fmt::Display> fmt::Debug for Point2D<T> {
 
 pub struct NeighborsIterator<T> {
     point: Point2D<T>,
-    current_direction: i32,
+    current_direction: usize,
 }
 
 impl<T> NeighborsIterator<T> {
@@ -150,58 +483,44 @@ impl<T> NeighborsIterator<T> {
     }
 }
 
-impl<T: Add<Output = T> + Sub<Output = T> + One + Copy> Iterator for NeighborsIterator<T> {
+impl<T: CheckedAdd + CheckedSub + One + Copy> Iterator for NeighborsIterator<T> {
     type Item = Point2D<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let neighbor_point = {
-            let one = T::one();
-            match self.current_direction {
-                0 => Self::Item {
-                    x: self.point.x,
-                    y: self.point.y + one,
-                },
-                1 => Self::Item {
-                    x: self.point.x,
-                    y: self.point.y - one,
-                },
-                2 => Self::Item {
-                    x: self.point.x + one,
-                    y: self.point.y,
-                },
-                3 => Self::Item {
-                    x: self.point.x - one,
-                    y: self.point.y,
-                },
-                4 => Self::Item {
-                    x: self.point.x + one,
-                    y: self.point.y + one,
-                },
-                5 => Self::Item {
-                    x: self.point.x - one,
-                    y: self.point.y - one,
-                },
-                6 => Self::Item {
-                    x: self.point.x + one,
-                    y: self.point.y - one,
-                },
-                7 => Self::Item {
-                    x: self.point.x - one,
-                    y: self.point.y + one,
-                },
-                _ => return None,
+        while let Some(dir) = NEIGHBOR_ORDER.get(self.current_direction) {
+            self.current_direction += 1;
+            if let Some(neighbor) = checked_neighbor(self.point, *dir) {
+                return Some(neighbor);
             }
-        };
-
-        self.current_direction += 1;
-
-        Some(neighbor_point)
+        }
+        None
     }
 }
 
+// The order in which `NeighborsIterator` visits the neighbors.
+const NEIGHBOR_ORDER: [Direction8; 8] = [
+    Direction8::South,
+    Direction8::North,
+    Direction8::East,
+    Direction8::West,
+    Direction8::SouthEast,
+    Direction8::NorthWest,
+    Direction8::NorthEast,
+    Direction8::SouthWest,
+];
+
+// The order in which `CardinalNeighborsIterator` visits the neighbors,
+// which is reading order.
+const CARDINAL_NEIGHBOR_ORDER: [CardinalDirection; 4] = [
+    CardinalDirection::North,
+    CardinalDirection::West,
+    CardinalDirection::East,
+    CardinalDirection::South,
+];
+
 pub struct CardinalNeighborsIterator<T> {
     point: Point2D<T>,
-    current_direction: u8,
+    current_direction: usize,
 }
 
 impl<T> CardinalNeighborsIterator<T> {
@@ -213,36 +532,1230 @@ impl<T> CardinalNeighborsIterator<T> {
     }
 }
 
-impl<T: Add<Output = T> + Sub<Output = T> + From<u8> + Copy> Iterator
-    for CardinalNeighborsIterator<T>
-{
+impl<T: CheckedAdd + CheckedSub + One + Copy> Iterator for CardinalNeighborsIterator<T> {
     type Item = Point2D<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let neighbor_point = {
-            match self.current_direction {
-                0 => Self::Item {
-                    x: self.point.x,
-                    y: self.point.y - T::from(1),
-                },
-                1 => Self::Item {
-                    x: self.point.x - T::from(1),
-                    y: self.point.y,
-                },
-                2 => Self::Item {
-                    x: self.point.x + T::from(1),
-                    y: self.point.y,
-                },
-                3 => Self::Item {
-                    x: self.point.x,
-                    y: self.point.y + T::from(1),
-                },
-                _ => return None,
+        while let Some(dir) = CARDINAL_NEIGHBOR_ORDER.get(self.current_direction) {
+            self.current_direction += 1;
+            if let Some(neighbor) = checked_neighbor(self.point, (*dir).into()) {
+                return Some(neighbor);
+            }
+        }
+        None
+    }
+}
+
+// Returns the neighbor of `point` in direction `dir`, or None if either
+// coordinate overflows.
+fn checked_neighbor<T: CheckedAdd + CheckedSub + One + Copy>(
+    point: Point2D<T>,
+    dir: Direction8,
+) -> Option<Point2D<T>> {
+    let (dx, dy) = dir.unit();
+    Some(Point2D {
+        x: checked_shift(point.x, dx)?,
+        y: checked_shift(point.y, dy)?,
+    })
+}
+
+// Moves `value` one unit in the direction of the sign of `delta`, returning
+// None if that overflows.
+fn checked_shift<T: CheckedAdd + CheckedSub + One + Copy>(value: T, delta: i8) -> Option<T> {
+    match delta.signum() {
+        -1 => value.checked_sub(&T::one()),
+        1 => value.checked_add(&T::one()),
+        _ => Some(value),
+    }
+}
+
+// Moves `value` by `n` in the direction of the sign of `delta`.
+fn shift<T: Add<Output = T> + Sub<Output = T> + Copy>(value: T, delta: i8, n: T) -> T {
+    match delta.signum() {
+        -1 => value - n,
+        1 => value + n,
+        _ => value,
+    }
+}
+
+// Defines a point type with `$n` coordinates, mirroring `Point2D`.  The
+// fields are declared in the order given by `$decl`, most significant
+// first, so the derived `Ord` compares the last coordinate first just as
+// `Point2D` compares y before x.
+macro_rules! point_type {
+    ($name:ident, $iter:ident, $n:literal, [$($field:ident),+], [$($decl:ident),+]) => {
+        #[derive(Default, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name<T> {
+            $(pub $decl: T,)+
+        }
+
+        impl<T: Add<Output = T> + Sub<Output = T> + Ord + Copy + TryFrom<usize> + TryInto<usize> + One>
+            $name<T>
+        {
+            #[doc = concat!("Creates a new [`", stringify!($name), "<T>`].")]
+            pub fn new($($field: T),+) -> $name<T> {
+                $name { $($field),+ }
+            }
+
+            #[doc = concat!("Creates a new [`", stringify!($name), "<T>`] from `usize`")]
+            /// arguments.  Returns None if the integral conversion fails.
+            pub fn usize_new($($field: usize),+) -> Option<$name<T>> {
+                Some(Self::new($($field.try_into().ok()?),+))
+            }
+
+            /// Returns the coordinates of this point as usize, starting
+            /// with x.
+            pub fn as_usize_array(&self) -> Option<[usize; $n]> {
+                Some([$(self.$field.try_into().ok()?),+])
+            }
+
+            pub fn manhattan_distance(&self, other: Self) -> T {
+                let mut distances = [$(abs_difference(self.$field, other.$field)),+].into_iter();
+                let first = distances.next().unwrap();
+                distances.fold(first, |sum, d| sum + d)
+            }
+
+            #[doc = concat!("Returns the ", stringify!($n), " x 2 points that differ from this one")]
+            /// by one in a single coordinate, skipping any that would
+            /// overflow.
+            pub fn cardinal_neighbors(&self) -> $iter<T> {
+                $iter::new(*self, false)
+            }
+
+            #[doc = concat!("Returns the 3^", stringify!($n), " - 1 points that differ from this")]
+            /// one by at most one in every coordinate, skipping any that
+            /// would overflow.
+            pub fn neighbors(&self) -> $iter<T> {
+                $iter::new(*self, true)
+            }
+        }
+
+        impl<T: Add<Output = T>> Add for $name<T> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self::Output {
+                Self {
+                    $($field: self.$field + other.$field),+
+                }
+            }
+        }
+
+        impl<T: Sub<Output = T>> Sub for $name<T> {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self::Output {
+                Self {
+                    $($field: self.$field - other.$field),+
+                }
+            }
+        }
+
+        impl<T: fmt::Display> fmt::Display for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let coordinates = [$(&self.$field),+];
+                write!(f, "(")?;
+                for (i, c) in coordinates.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+                write!(f, ")")
+            }
+        }
+
+        impl<T: fmt::Display> fmt::Debug for $name<T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self)
+            }
+        }
+
+        pub struct $iter<T> {
+            point: $name<T>,
+            diagonal: bool,
+            index: usize,
+        }
+
+        impl<T> $iter<T> {
+            fn new(point: $name<T>, diagonal: bool) -> $iter<T> {
+                $iter {
+                    point,
+                    diagonal,
+                    index: 0,
+                }
+            }
+        }
+
+        impl<T: CheckedAdd + CheckedSub + One + Copy> Iterator for $iter<T> {
+            type Item = $name<T>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                loop {
+                    let mut offsets = [0_i8; $n];
+                    if self.diagonal {
+                        // Count in base 3, with digits -1, 0 and 1 for each
+                        // coordinate, skipping the all zero middle.
+                        let middle = (3_usize.pow($n) - 1) / 2;
+                        if self.index == middle {
+                            self.index += 1;
+                        }
+                        if self.index >= 3_usize.pow($n) {
+                            return None;
+                        }
+                        let mut rest = self.index;
+                        for offset in offsets.iter_mut() {
+                            *offset = (rest % 3) as i8 - 1;
+                            rest /= 3;
+                        }
+                    } else {
+                        if self.index >= 2 * $n {
+                            return None;
+                        }
+                        offsets[self.index / 2] = if self.index % 2 == 0 { -1 } else { 1 };
+                    }
+                    self.index += 1;
+
+                    let mut offsets = offsets.into_iter();
+                    let neighbor = (|| {
+                        Some($name {
+                            $($field: checked_shift(self.point.$field, offsets.next().unwrap())?),+
+                        })
+                    })();
+                    if neighbor.is_some() {
+                        return neighbor;
+                    }
+                }
+            }
+        }
+    };
+}
+
+point_type!(Point3D, Neighbors3DIterator, 3, [x, y, z], [z, y, x]);
+point_type!(Point4D, Neighbors4DIterator, 4, [x, y, z, w], [w, z, y, x]);
+
+/// A dense rectangular grid of `T` indexed by `Point2D<usize>`, stored row
+/// by row.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Creates a `width` by `height` grid with every cell set to `value`.
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Creates a `width` by `height` grid with each cell set to `f` of its
+    /// position, calling `f` in reading order.
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Point2D<usize>) -> T) -> Grid<T> {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Point2D { x, y }))
+            .map(&mut f)
+            .collect();
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Parses a map with one character per cell, converting each with `f`.
+    /// Blank lines are skipped and every other line must be as long as the
+    /// first.  A character for which `f` returns None is an error.
+    pub fn parse(input: &str, mut f: impl FnMut(char) -> Option<T>) -> Result<Grid<T>, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
             }
+            let mut count = 0;
+            for (index, ch) in line.char_indices() {
+                let cell = f(ch).ok_or_else(|| {
+                    ParseError::new(
+                        i,
+                        line,
+                        &line[index..index + ch.len_utf8()],
+                        "unexpected character",
+                    )
+                })?;
+                cells.push(cell);
+                count += 1;
+            }
+            match width {
+                None => width = Some(count),
+                Some(width) if width != count => {
+                    return Err(ParseError::line(
+                        i,
+                        line,
+                        format!("expected {} characters, like the first line", width),
+                    ));
+                }
+                Some(_) => {}
+            }
+            height += 1;
+        }
+        Ok(Grid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, pos: Point2D<usize>) -> bool {
+        pos.x < self.width && pos.y < self.height
+    }
+
+    fn index_of(&self, pos: Point2D<usize>) -> Option<usize> {
+        self.contains(pos).then(|| pos.y * self.width + pos.x)
+    }
+
+    pub fn get(&self, pos: Point2D<usize>) -> Option<&T> {
+        self.index_of(pos).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, pos: Point2D<usize>) -> Option<&mut T> {
+        self.index_of(pos).map(|i| &mut self.cells[i])
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        (y < self.height).then(|| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
+        (y < self.height).then(|| &mut self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    /// Iterates over the cells in column `x` from top to bottom.  Empty if
+    /// `x` is out of range.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        let cells = if x < self.width {
+            &self.cells[x..]
+        } else {
+            &[]
         };
+        cells.iter().step_by(self.width.max(1))
+    }
+
+    /// Iterates over the rows from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on a zero width, which has no cells anyway.
+        self.cells.chunks(self.width.max(1))
+    }
 
-        self.current_direction += 1;
+    /// Iterates over the columns from left to right.
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    fn position_of(width: usize, index: usize) -> Point2D<usize> {
+        Point2D {
+            x: index % width,
+            y: index / width,
+        }
+    }
+
+    /// Iterates over the positions of the cells in reading order.
+    pub fn positions(&self) -> impl Iterator<Item = Point2D<usize>> + use<T> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| Grid::<T>::position_of(width, i))
+    }
+
+    /// Iterates over the cells and their positions in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point2D<usize>, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// Returns the position of the first cell after `after` in reading order
+    /// for which `occupied` returns true, searching from the first cell if
+    /// `after` is None.
+    pub fn next_after(
+        &self,
+        after: Option<Point2D<usize>>,
+        mut occupied: impl FnMut(&T) -> bool,
+    ) -> Option<Point2D<usize>> {
+        let start = match after {
+            None => 0,
+            Some(p) => p.y.min(self.height) * self.width + (p.x + 1).min(self.width),
+        };
+        let offset = self.cells.get(start..)?.iter().position(&mut occupied)?;
+        Some(Grid::<T>::position_of(self.width, start + offset))
+    }
+
+    /// Iterates over the cells in reading order.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    /// Renders the grid with one character per cell given by `glyph`.
+    pub fn render(&self, glyph: impl Fn(&T) -> char) -> String {
+        let mut s = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            s.extend(row.iter().map(&glyph));
+            s.push('\n');
+        }
+        s
+    }
+}
+
+impl<T> Index<Point2D<usize>> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Point2D<usize>) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{} is outside the grid", pos))
+    }
+}
+
+impl<T> IndexMut<Point2D<usize>> for Grid<T> {
+    fn index_mut(&mut self, pos: Point2D<usize>) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{} is outside the grid", pos))
+    }
+}
+
+/// Displays each row on its own line, with cells displayed one after the
+/// other.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Grid")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("cells", &self.cells)
+            .finish()
+    }
+}
+
+/// An axis-aligned rectangle of the points from `min` to `max` inclusive.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect<T> {
+    pub min: Point2D<T>,
+    pub max: Point2D<T>,
+}
+
+impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Ord + Copy + One> Rect<T> {
+    /// Creates the smallest rectangle with corners `a` and `b`.
+    pub fn new(a: Point2D<T>, b: Point2D<T>) -> Rect<T> {
+        Rect {
+            min: Point2D {
+                x: a.x.min(b.x),
+                y: a.y.min(b.y),
+            },
+            max: Point2D {
+                x: a.x.max(b.x),
+                y: a.y.max(b.y),
+            },
+        }
+    }
+
+    /// Creates a rectangle `width` by `height` points with its top left
+    /// corner at `origin`.  Panics if `width` or `height` is zero.
+    pub fn from_size(origin: Point2D<T>, width: T, height: T) -> Rect<T> {
+        assert!(width >= T::one() && height >= T::one(), "empty rectangle");
+        Rect {
+            min: origin,
+            max: Point2D {
+                x: origin.x + width - T::one(),
+                y: origin.y + height - T::one(),
+            },
+        }
+    }
+
+    /// Returns the smallest rectangle containing all of `points`, or None
+    /// if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Point2D<T>>) -> Option<Rect<T>> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Rect::new(first, first), |rect, p| rect.including(p)))
+    }
+
+    pub fn width(&self) -> T {
+        self.max.x - self.min.x + T::one()
+    }
+
+    pub fn height(&self) -> T {
+        self.max.y - self.min.y + T::one()
+    }
+
+    /// Returns the number of points in the rectangle.
+    pub fn area(&self) -> T {
+        self.width() * self.height()
+    }
+
+    pub fn contains(&self, p: Point2D<T>) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+
+    /// Returns the points in both rectangles, or None if there are none.
+    pub fn intersection(&self, other: &Rect<T>) -> Option<Rect<T>> {
+        let min = Point2D {
+            x: self.min.x.max(other.min.x),
+            y: self.min.y.max(other.min.y),
+        };
+        let max = Point2D {
+            x: self.max.x.min(other.max.x),
+            y: self.max.y.min(other.max.y),
+        };
+        (min.x <= max.x && min.y <= max.y).then_some(Rect { min, max })
+    }
+
+    /// Returns the smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect<T>) -> Rect<T> {
+        self.including(other.min).including(other.max)
+    }
+
+    /// Returns the smallest rectangle containing this one and `p`.
+    pub fn including(&self, p: Point2D<T>) -> Rect<T> {
+        Rect {
+            min: Point2D {
+                x: self.min.x.min(p.x),
+                y: self.min.y.min(p.y),
+            },
+            max: Point2D {
+                x: self.max.x.max(p.x),
+                y: self.max.y.max(p.y),
+            },
+        }
+    }
+
+    /// Returns whether `p` lies on the edge of the rectangle.
+    pub fn on_edge(&self, p: Point2D<T>) -> bool {
+        self.contains(p)
+            && (p.x == self.min.x || p.x == self.max.x || p.y == self.min.y || p.y == self.max.y)
+    }
+
+    /// Iterates over the points in the rectangle in reading order.
+    pub fn points(&self) -> RectPoints<T> {
+        RectPoints {
+            rect: *self,
+            next: Some(self.min),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Rect<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} to {}", self.min, self.max)
+    }
+}
+
+impl<T: fmt::Display> fmt::Debug for Rect<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+pub struct RectPoints<T> {
+    rect: Rect<T>,
+    next: Option<Point2D<T>>,
+}
+
+impl<T: Add<Output = T> + Ord + Copy + One> Iterator for RectPoints<T> {
+    type Item = Point2D<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = self.next?;
+        self.next = if p.x < self.rect.max.x {
+            Some(Point2D {
+                x: p.x + T::one(),
+                y: p.y,
+            })
+        } else if p.y < self.rect.max.y {
+            Some(Point2D {
+                x: self.rect.min.x,
+                y: p.y + T::one(),
+            })
+        } else {
+            None
+        };
+        Some(p)
+    }
+}
+
+/// Reading order: top to bottom, and left to right within a row, with `y`
+/// growing downwards.  This is also the derived `Ord` of `Point2D`, which
+/// compares `y` before `x`; `ReadingOrder` spells it out where the order
+/// matters to a puzzle.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadingOrder;
+
+impl ReadingOrder {
+    pub fn cmp<T: Ord>(a: &Point2D<T>, b: &Point2D<T>) -> Ordering {
+        a.y.cmp(&b.y).then_with(|| a.x.cmp(&b.x))
+    }
+
+    /// Returns the first key of `map` after `after` in reading order, or its
+    /// first key if `after` is None.
+    pub fn next_key_after<T: Ord + Copy, V>(
+        map: &BTreeMap<Point2D<T>, V>,
+        after: Option<Point2D<T>>,
+    ) -> Option<Point2D<T>> {
+        match after {
+            None => map.keys().next().copied(),
+            Some(p) => map.range((Excluded(p), Unbounded)).next().map(|(k, _)| *k),
+        }
+    }
+}
+
+/// Hands out turns in reading order for a round in which the occupants of a
+/// map act one at a time, possibly moving or removing one another, as the
+/// carts of day 13 and the units of day 15 do.  An occupant that moves to a
+/// later position is not given a second turn, and one removed before its
+/// turn gets none.
+#[derive(Clone)]
+pub struct TurnOrder<T> {
+    last: Option<Point2D<T>>,
+    finished: HashSet<Point2D<T>>,
+}
+
+impl<T> Default for TurnOrder<T> {
+    fn default() -> Self {
+        TurnOrder {
+            last: None,
+            finished: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> TurnOrder<T> {
+    pub fn new() -> TurnOrder<T> {
+        TurnOrder::default()
+    }
+
+    /// Returns the position of the next occupant to act, or None when the
+    /// round is over.  `next_occupied_after` finds the first occupied
+    /// position after a point in reading order, or the first of all for
+    /// None, as [`Grid::next_after`] and [`ReadingOrder::next_key_after`] do.
+    pub fn next(
+        &mut self,
+        mut next_occupied_after: impl FnMut(Option<Point2D<T>>) -> Option<Point2D<T>>,
+    ) -> Option<Point2D<T>> {
+        loop {
+            let pos = next_occupied_after(self.last)?;
+            self.last = Some(pos);
+            if !self.finished.contains(&pos) {
+                return Some(pos);
+            }
+        }
+    }
+
+    /// Records that the occupant whose turn it was ended it at `pos`.
+    pub fn finished_at(&mut self, pos: Point2D<T>) {
+        self.finished.insert(pos);
+    }
+}
+
+/// A grid of `T` at scattered positions anywhere in the plane, with signed
+/// coordinates of type `C`.  It keeps track of the bounding box of its
+/// points, growing it as they are inserted and recomputing it only when
+/// asked for after a point on its edge was removed.
+pub struct SparseGrid<T, C = i32> {
+    cells: HashMap<Point2D<C>, T>,
+    // The bounding box of the cells, or None if there are none.  Stale
+    // while `bounds_dirty` is set.
+    bounds: Cell<Option<Rect<C>>>,
+    bounds_dirty: Cell<bool>,
+}
+
+impl<T, C> Default for SparseGrid<T, C> {
+    fn default() -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+            bounds: Cell::new(None),
+            bounds_dirty: Cell::new(false),
+        }
+    }
+}
+
+impl<T, C: Signed + Ord + Copy + Hash> SparseGrid<T, C> {
+    pub fn new() -> SparseGrid<T, C> {
+        SparseGrid::default()
+    }
+
+    /// Sets the cell at `pos`, returning its previous value.
+    pub fn insert(&mut self, pos: Point2D<C>, value: T) -> Option<T> {
+        if !self.bounds_dirty.get() {
+            self.bounds.set(Some(match self.bounds.get() {
+                None => Rect::new(pos, pos),
+                Some(bounds) => bounds.including(pos),
+            }));
+        }
+        self.cells.insert(pos, value)
+    }
+
+    /// Clears the cell at `pos`, returning its value.  The bounds shrink if
+    /// `pos` was on their edge.
+    pub fn remove(&mut self, pos: Point2D<C>) -> Option<T> {
+        let value = self.cells.remove(&pos)?;
+        if self.bounds.get().is_some_and(|bounds| bounds.on_edge(pos)) {
+            self.bounds_dirty.set(true);
+        }
+        Some(value)
+    }
+
+    pub fn get(&self, pos: Point2D<C>) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    pub fn get_mut(&mut self, pos: Point2D<C>) -> Option<&mut T> {
+        self.cells.get_mut(&pos)
+    }
+
+    pub fn contains(&self, pos: Point2D<C>) -> bool {
+        self.cells.contains_key(&pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns the smallest rectangle containing the cells, or None if the
+    /// grid is empty.
+    pub fn bounds(&self) -> Option<Rect<C>> {
+        if self.bounds_dirty.replace(false) {
+            self.bounds
+                .set(Rect::from_points(self.cells.keys().copied()));
+        }
+        self.bounds.get()
+    }
+
+    /// Iterates over the cells in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point2D<C>, &T)> {
+        self.cells.iter().map(|(pos, value)| (*pos, value))
+    }
+
+    /// Moves every cell by `offset`.
+    pub fn translate(&mut self, offset: Point2D<C>) {
+        self.cells = std::mem::take(&mut self.cells)
+            .into_iter()
+            .map(|(pos, value)| (pos + offset, value))
+            .collect();
+        self.bounds.set(self.bounds.get().map(|bounds| Rect {
+            min: bounds.min + offset,
+            max: bounds.max + offset,
+        }));
+    }
+
+    /// Renders the bounding box of the cells with one character per
+    /// position given by `glyph`, which receives None for empty positions.
+    pub fn render(&self, glyph: impl Fn(Option<&T>) -> char) -> String {
+        let mut s = String::new();
+        if let Some(bounds) = self.bounds() {
+            for pos in bounds.points() {
+                s.push(glyph(self.get(pos)));
+                if pos.x == bounds.max.x {
+                    s.push('\n');
+                }
+            }
+        }
+        s
+    }
+}
+
+impl<T: Clone, C: Copy> Clone for SparseGrid<T, C> {
+    fn clone(&self) -> Self {
+        SparseGrid {
+            cells: self.cells.clone(),
+            bounds: self.bounds.clone(),
+            bounds_dirty: self.bounds_dirty.clone(),
+        }
+    }
+}
+
+/// Grids are equal when their cells are, whether or not their bounds have
+/// been recomputed.
+impl<T: PartialEq, C: Eq + Hash> PartialEq for SparseGrid<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+}
+
+impl<T: Eq, C: Eq + Hash> Eq for SparseGrid<T, C> {}
+
+impl<T, C: Signed + Ord + Copy + Hash> FromIterator<(Point2D<C>, T)> for SparseGrid<T, C> {
+    fn from_iter<I: IntoIterator<Item = (Point2D<C>, T)>>(iter: I) -> Self {
+        let mut grid = SparseGrid::new();
+        grid.extend(iter);
+        grid
+    }
+}
+
+impl<T, C: Signed + Ord + Copy + Hash> Extend<(Point2D<C>, T)> for SparseGrid<T, C> {
+    fn extend<I: IntoIterator<Item = (Point2D<C>, T)>>(&mut self, iter: I) {
+        for (pos, value) in iter {
+            self.insert(pos, value);
+        }
+    }
+}
+
+impl<T: fmt::Debug, C: Signed + Ord + Copy + Hash + fmt::Display> fmt::Debug for SparseGrid<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SparseGrid")
+            .field("bounds", &self.bounds())
+            .field("cells", &self.cells)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        let mut grid = Grid::parse("#.#\n\n..#\n", |ch| match ch {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(Point2D::new(2, 1)), Some(&true));
+        assert_eq!(grid.get(Point2D::new(3, 0)), None);
+        assert_eq!(grid.get(Point2D::new(0, 2)), None);
+        *grid.get_mut(Point2D::new(0, 1)).unwrap() = true;
+        grid[Point2D::new(1, 0)] = true;
+        assert_eq!(grid.row(0), Some(&[true, true, true][..]));
+        assert_eq!(grid.row(2), None);
+        let column: Vec<_> = grid.column(1).copied().collect();
+        assert_eq!(column, vec![true, false]);
+        assert_eq!(grid.columns().count(), 3);
+        assert_eq!(grid.column(3).count(), 0);
+        let set: Vec<_> = grid.iter().filter(|&(_, &c)| c).map(|(p, _)| p).collect();
+        assert_eq!(set.len(), 5);
+        assert_eq!(set[3], Point2D::new(0, 1));
+        assert_eq!(grid.render(|&c| if c { '#' } else { '.' }), "###\n#.#\n");
+        assert_eq!(
+            Grid::from_fn(2, 2, |p| p.x + 2 * p.y).to_string(),
+            "01\n23\n"
+        );
+        assert_eq!(Grid::new(2, 1, 'x').values().count(), 2);
+    }
+
+    #[test]
+    fn test_rect() {
+        let a = Rect::from_size(Point2D::new(1, 3), 4, 4);
+        let b = Rect::from_size(Point2D::new(3, 1), 4, 4);
+        let c = Rect::from_size(Point2D::new(5, 5), 2, 2);
+        assert_eq!(a, Rect::new(Point2D::new(4, 3), Point2D::new(1, 6)));
+        assert_eq!((a.width(), a.height(), a.area()), (4, 4, 16));
+        assert_eq!(
+            a.intersection(&b),
+            Some(Rect::from_size(Point2D::new(3, 3), 2, 2))
+        );
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(b.intersection(&c), None);
+        assert_eq!(
+            a.union(&c),
+            Rect::new(Point2D::new(1, 3), Point2D::new(6, 6))
+        );
+        assert!(a.contains(Point2D::new(4, 6)));
+        assert!(!a.contains(Point2D::new(5, 6)));
+        assert!(a.on_edge(Point2D::new(1, 4)));
+        assert!(!a.on_edge(Point2D::new(2, 4)));
+        assert_eq!(a.to_string(), "(1, 3) to (4, 6)");
+    }
+
+    #[test]
+    fn test_rect_points() {
+        let points: Vec<Point2D<usize>> =
+            vec![Point2D::new(2, 0), Point2D::new(0, 1), Point2D::new(1, 1)];
+        let rect = Rect::from_points(points.iter().copied()).unwrap();
+        assert_eq!(rect, Rect::new(Point2D::new(0, 0), Point2D::new(2, 1)));
+        let inside: Vec<_> = rect.points().collect();
+        assert_eq!(inside.len(), rect.area());
+        assert_eq!(
+            inside[..4],
+            [
+                Point2D::new(0, 0),
+                Point2D::new(1, 0),
+                Point2D::new(2, 0),
+                Point2D::new(0, 1)
+            ]
+        );
+        assert!(inside.is_sorted());
+        assert_eq!(Rect::<i32>::from_points([]), None);
+        let single = Rect::from_points([Point2D::new(-3, -3)]).unwrap();
+        assert_eq!(single.points().count(), 1);
+    }
+
+    #[test]
+    fn test_reading_order() {
+        let mut points = vec![Point2D::new(2, 0), Point2D::new(0, 1), Point2D::new(1, 0)];
+        points.sort_by(ReadingOrder::cmp);
+        assert_eq!(
+            points,
+            vec![Point2D::new(1, 0), Point2D::new(2, 0), Point2D::new(0, 1)]
+        );
+        let mut sorted = points.clone();
+        sorted.sort();
+        assert_eq!(sorted, points);
+    }
+
+    #[test]
+    fn test_grid_next_after() {
+        let grid = Grid::parse("x..\n..x\nx..\n", |ch| Some(ch == 'x')).unwrap();
+        let occupied = |&x: &bool| x;
+        assert_eq!(grid.next_after(None, occupied), Some(Point2D::new(0, 0)));
+        let after = Some(Point2D::new(0, 0));
+        assert_eq!(grid.next_after(after, occupied), Some(Point2D::new(2, 1)));
+        let after = Some(Point2D::new(2, 1));
+        assert_eq!(grid.next_after(after, occupied), Some(Point2D::new(0, 2)));
+        assert_eq!(grid.next_after(Some(Point2D::new(0, 2)), occupied), None);
+        // Positions past the end of a row continue with the next row.
+        let after = Some(Point2D::new(7, 0));
+        assert_eq!(grid.next_after(after, occupied), Some(Point2D::new(2, 1)));
+        assert_eq!(grid.next_after(Some(Point2D::new(0, 9)), occupied), None);
+        assert_eq!(grid.positions().nth(4), Some(Point2D::new(1, 1)));
+    }
+
+    #[test]
+    fn test_turn_order() {
+        // Each occupant moves four to the right.  The first removes the one
+        // at 3 and lands before the one at 6, but must not go again.
+        let mut map: BTreeMap<Point2D<i32>, char> = [(1, 'a'), (3, 'b'), (6, 'c')]
+            .into_iter()
+            .map(|(x, c)| (Point2D::new(x, 0), c))
+            .collect();
+        let mut order = TurnOrder::new();
+        let mut turns = Vec::new();
+        while let Some(pos) = order.next(|after| ReadingOrder::next_key_after(&map, after)) {
+            let c = map.remove(&pos).unwrap();
+            turns.push(c);
+            let to = pos + Point2D::new(4, 0);
+            if c == 'a' {
+                map.remove(&Point2D::new(3, 0));
+            }
+            map.insert(to, c);
+            order.finished_at(to);
+        }
+        assert_eq!(turns, vec!['a', 'c']);
+        assert_eq!(map.keys().map(|p| p.x).collect::<Vec<_>>(), vec![5, 10]);
+    }
+
+    #[test]
+    fn test_sparse_grid() {
+        let mut grid: SparseGrid<char> = [(Point2D::new(-1, 2), 'a'), (Point2D::new(1, 0), 'b')]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            grid.bounds(),
+            Some(Rect::new(Point2D::new(-1, 0), Point2D::new(1, 2)))
+        );
+        let glyph = |c: Option<&char>| c.copied().unwrap_or('.');
+        assert_eq!(grid.render(glyph), "..b\n...\na..\n");
+
+        grid.insert(Point2D::new(0, 3), 'c');
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid.remove(Point2D::new(-1, 2)), Some('a'));
+        assert_eq!(grid.remove(Point2D::new(-1, 2)), None);
+        assert_eq!(
+            grid.bounds(),
+            Some(Rect::new(Point2D::new(0, 0), Point2D::new(1, 3)))
+        );
+
+        grid.translate(Point2D::new(-10, 5));
+        assert_eq!(grid.get(Point2D::new(-9, 5)), Some(&'b'));
+        assert!(!grid.contains(Point2D::new(1, 0)));
+        assert_eq!(
+            grid.bounds(),
+            Some(Rect::new(Point2D::new(-10, 5), Point2D::new(-9, 8)))
+        );
+        assert_eq!(grid.render(glyph), ".b\n..\n..\nc.\n");
+
+        assert_eq!(SparseGrid::<()>::new().bounds(), None);
+        assert_eq!(SparseGrid::<()>::new().render(|_| 'x'), "");
+
+        // Removing from the edge and then inserting recomputes the bounds
+        // from every cell once they are asked for.
+        let mut wide: SparseGrid<u8, i64> = (-3..=3).map(|x| (Point2D::new(x, 0), 0)).collect();
+        wide.remove(Point2D::new(3, 0));
+        wide.remove(Point2D::new(-3, 0));
+        wide.insert(Point2D::new(0, -1), 1);
+        assert_eq!(
+            wide.bounds(),
+            Some(Rect::new(Point2D::new(-2, -1), Point2D::new(2, 0)))
+        );
+        for x in -2..=2 {
+            wide.remove(Point2D::new(x, 0));
+        }
+        wide.remove(Point2D::new(0, -1));
+        assert_eq!(wide.bounds(), None);
+        assert_eq!(wide, SparseGrid::new());
+    }
+
+    #[test]
+    fn test_grid_parse_error() {
+        let parse = |input| Grid::parse(input, |ch| ch.to_digit(10));
+        let err = parse("12\n3x\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+        let err = parse("12\n345\n").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(parse("").unwrap().width(), 0);
+    }
+
+    #[test]
+    fn test_cardinal_direction_from_char() {
+        for dir in CARDINAL_DIRECTIONS {
+            assert_eq!(CardinalDirection::from_char(dir.arrow()), Some(dir));
+        }
+        assert_eq!(
+            CardinalDirection::from_char('W'),
+            Some(CardinalDirection::West)
+        );
+        assert_eq!(CardinalDirection::from_char('x'), None);
+    }
+
+    #[test]
+    fn test_direction8() {
+        let ne = Direction8::NorthEast;
+        assert_eq!(ne.right45(), Direction8::East);
+        assert_eq!(ne.left45(), Direction8::North);
+        assert_eq!(ne.right(), Direction8::SouthEast);
+        assert_eq!(ne.left(), Direction8::NorthWest);
+        assert_eq!(ne.negate(), Direction8::SouthWest);
+        assert_eq!(Direction8::NorthWest.right45(), Direction8::North);
+        assert!(ne.is_diagonal());
+        assert_eq!(ne.cardinal(), None);
+        assert_eq!(Direction8::West.cardinal(), Some(CardinalDirection::West));
+        for dir in DIRECTIONS_8 {
+            assert_eq!(Direction8::from_offset(dir.offset::<i32>()), Some(dir));
+            assert_eq!(dir.right().left(), dir);
+        }
+        for dir in CARDINAL_DIRECTIONS {
+            assert_eq!(Direction8::from(dir).cardinal(), Some(dir));
+        }
+        assert_eq!(ne.offset::<i64>(), Point2D::new(1, -1));
+        assert_eq!(Direction8::from_offset(Point2D::new(0, 0)), None);
+        assert_eq!(Direction8::from_offset(Point2D::new(2, 0)), None);
+        assert_eq!(Direction8::from_offset(Point2D::new(200, 0)), None);
+    }
+
+    #[test]
+    fn test_step() {
+        let p = Point2D::<usize>::new(5, 5);
+        assert_eq!(p.step(Direction8::SouthWest, 3), Point2D::new(2, 8));
+        assert_eq!(p.step(CardinalDirection::North, 5), Point2D::new(5, 0));
+        assert_eq!(
+            p.cardinal_neighbor(CardinalDirection::East),
+            Point2D::new(6, 5)
+        );
+        let q = Point2D::<i32>::new(0, 0);
+        assert_eq!(q.step(Direction8::NorthWest, 2), Point2D::new(-2, -2));
+        let neighbors: Vec<_> = q.neighbors().collect();
+        assert_eq!(neighbors[0], Point2D::new(0, 1));
+        assert_eq!(neighbors[7], Point2D::new(-1, 1));
+        assert_eq!(neighbors.len(), 8);
+    }
+
+    #[test]
+    fn test_distances() {
+        let a = Point2D::<i32>::new(1, -2);
+        let b = Point2D::<i32>::new(-3, 1);
+        assert_eq!(a.chebyshev_distance(b), 4);
+        assert_eq!(a.squared_euclidean_distance(b), 25);
+        let c = Point2D::<usize>::new(7, 2);
+        let d = Point2D::<usize>::new(4, 6);
+        assert_eq!(c.chebyshev_distance(d), 4);
+        assert_eq!(d.chebyshev_distance(c), 4);
+        assert_eq!(c.squared_euclidean_distance(d), 25);
+        assert_eq!(c.manhattan_distance(d), 7);
+    }
+
+    #[test]
+    fn test_rotate_signed() {
+        let p = Point2D::<i64>::new(3, -1);
+        // North-east of the origin turns to south-east, then south-west.
+        assert_eq!(p.rotate_right(), Point2D::new(1, 3));
+        assert_eq!(p.rotate_right().rotate_right(), -p);
+        assert_eq!(p.rotate_left(), Point2D::new(-1, -3));
+        assert_eq!(p.rotate_left().rotate_right(), p);
+        let center = Point2D::new(1, 1);
+        assert_eq!(p.rotate_right_around(center), Point2D::new(3, 3));
+        assert_eq!(p.rotate_left_around(center), Point2D::new(-1, -1));
+        let north = Direction8::North.offset::<i64>();
+        assert_eq!(north.rotate_right(), Direction8::East.offset());
+        assert_eq!(north.rotate_left(), Direction8::West.offset());
+    }
+
+    #[test]
+    fn test_rotate_unsigned() {
+        let center = Point2D::<usize>::new(5, 5);
+        let p = Point2D::<usize>::new(7, 4);
+        assert_eq!(p.rotate_right_around(center), Point2D::new(6, 7));
+        assert_eq!(p.rotate_left_around(center), Point2D::new(4, 3));
+        assert_eq!(p.rotate_right_around(center).rotate_left_around(center), p);
+        assert_eq!(Point2D::<u32>::new(0, 0).rotate_right(), Point2D::zero());
+    }
+
+    #[test]
+    fn test_reflect() {
+        let p = Point2D::<i32>::new(3, -1);
+        assert_eq!(p.reflect_x(0), Point2D::new(-3, -1));
+        assert_eq!(p.reflect_y(0), Point2D::new(3, 1));
+        assert_eq!(p.transpose(), Point2D::new(-1, 3));
+        let q = Point2D::<u8>::new(1, 4);
+        assert_eq!(q.reflect_x(2), Point2D::new(3, 4));
+        assert_eq!(q.reflect_y(3), Point2D::new(1, 2));
+        assert_eq!(q.transpose(), Point2D::new(4, 1));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut p = Point2D::<i32>::new(2, -3);
+        assert_eq!(-p, Point2D::new(-2, 3));
+        assert_eq!(p * -2, Point2D::new(-4, 6));
+        p += Point2D::new(1, 1);
+        assert_eq!(p, Point2D::new(3, -2));
+        p -= Point2D::new(3, 3);
+        assert_eq!(p, Point2D::new(0, -5));
+
+        let mut q = Point2D::<usize>::new(2, 3);
+        assert_eq!(q * 3, Point2D::new(6, 9));
+        q += Point2D::new(1, 1);
+        q -= Point2D::new(3, 0);
+        assert_eq!(q, Point2D::new(0, 4));
+    }
+
+    #[test]
+    fn test_neighbors_within() {
+        let corner = Point2D::<usize>::new(0, 0);
+        let cardinal: Vec<_> = corner.cardinal_neighbors_within(3, 2).collect();
+        assert_eq!(cardinal, vec![Point2D::new(1, 0), Point2D::new(0, 1)]);
+        let all: Vec<_> = corner.neighbors_within(3, 2).collect();
+        assert_eq!(
+            all,
+            vec![Point2D::new(0, 1), Point2D::new(1, 0), Point2D::new(1, 1)]
+        );
+
+        let middle = Point2D::<usize>::new(1, 1);
+        assert_eq!(middle.cardinal_neighbors_within(3, 3).count(), 4);
+        assert_eq!(middle.neighbors_within(3, 3).count(), 8);
+        assert!(
+            middle
+                .cardinal_neighbors_within(3, 3)
+                .eq(middle.cardinal_neighbors())
+        );
+        assert!(middle.neighbors_within(3, 3).eq(middle.neighbors()));
+
+        let far = Point2D::<usize>::new(2, 1);
+        assert_eq!(
+            far.checked_cardinal_neighbor(CardinalDirection::East, 3, 2),
+            None
+        );
+        assert_eq!(
+            far.checked_cardinal_neighbor(CardinalDirection::North, 3, 2),
+            Some(Point2D::new(2, 0))
+        );
+        assert_eq!(
+            corner.checked_cardinal_neighbor(CardinalDirection::West, 3, 2),
+            None
+        );
+        let outside = Point2D::<i32>::new(-1, 0);
+        assert_eq!(outside.cardinal_neighbors_within(3, 2).count(), 0);
+    }
+
+    #[test]
+    fn test_neighbors_at_zero() {
+        let corner = Point2D::<usize>::new(0, 0);
+        let cardinal: Vec<_> = corner.cardinal_neighbors().collect();
+        assert_eq!(cardinal, vec![Point2D::new(1, 0), Point2D::new(0, 1)]);
+        let all: Vec<_> = corner.neighbors().collect();
+        assert_eq!(
+            all,
+            vec![Point2D::new(0, 1), Point2D::new(1, 0), Point2D::new(1, 1)]
+        );
+        let top = Point2D::<u8>::new(255, 0);
+        assert_eq!(top.cardinal_neighbors().count(), 2);
+
+        let origin = Point3D::<u32>::new(0, 0, 0);
+        assert_eq!(origin.cardinal_neighbors().count(), 3);
+        assert_eq!(origin.neighbors().count(), 7);
+    }
+
+    #[test]
+    fn test_point3d() {
+        let a = Point3D::new(1, -2, 3);
+        let b = Point3D::new(-1, 0, 5);
+        assert_eq!(a + b, Point3D::new(0, -2, 8));
+        assert_eq!(a - b, Point3D::new(2, -2, -2));
+        assert_eq!(a.manhattan_distance(b), 6);
+        assert_eq!(a.to_string(), "(1, -2, 3)");
+        assert!(Point3D::new(5, 5, 0) < Point3D::new(0, 0, 1));
+        assert_eq!(
+            Point3D::<u8>::usize_new(1, 2, 3),
+            Some(Point3D::new(1, 2, 3))
+        );
+        assert_eq!(Point3D::<u8>::usize_new(1, 2, 300), None);
+        assert_eq!(a.as_usize_array(), None);
+        assert_eq!(b.manhattan_distance(b), 0);
+    }
+
+    #[test]
+    fn test_point3d_neighbors() {
+        let origin = Point3D::new(0, 0, 0);
+        let cardinal: Vec<_> = origin.cardinal_neighbors().collect();
+        assert_eq!(cardinal.len(), 6);
+        assert!(cardinal.iter().all(|p| p.manhattan_distance(origin) == 1));
+        let all: std::collections::HashSet<_> = origin.neighbors().collect();
+        assert_eq!(all.len(), 26);
+        assert!(!all.contains(&origin));
+        assert!(all.contains(&Point3D::new(-1, 1, -1)));
+    }
 
-        Some(neighbor_point)
+    #[test]
+    fn test_point4d() {
+        let a = Point4D::new(0, 0, 0, 0);
+        let b = Point4D::new(3, 0, 0, 3);
+        assert_eq!(a.manhattan_distance(b), 6);
+        assert_eq!((b - a).to_string(), "(3, 0, 0, 3)");
+        assert_eq!(b.as_usize_array(), Some([3, 0, 0, 3]));
+        assert_eq!(a.cardinal_neighbors().count(), 8);
+        assert_eq!(a.neighbors().count(), 80);
+        let unsigned = Point4D::<u32>::new(1, 1, 1, 1);
+        assert!(
+            unsigned
+                .neighbors()
+                .all(|p| p.manhattan_distance(unsigned) <= 4)
+        );
     }
 }
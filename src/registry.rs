@@ -0,0 +1,152 @@
+use crate::solver::DynSolver;
+use crate::{
+    day01, day02, day03, day04, day05, day06, day07, day08, day09, day10, day11, day12, day13,
+    day14, day15, day16, day17, day18, day19, day20,
+};
+
+pub struct Day {
+    pub number: u8,
+    pub title: &'static str,
+    pub solver: &'static dyn DynSolver,
+}
+
+/// Every day with a [`Solver`](crate::solver::Solver), in day order.
+pub static DAYS: &[Day] = &[
+    Day {
+        number: 1,
+        title: "Chronal Calibration",
+        solver: &day01::Solution,
+    },
+    Day {
+        number: 2,
+        title: "Inventory Management System",
+        solver: &day02::Solution,
+    },
+    Day {
+        number: 3,
+        title: "No Matter How You Slice It",
+        solver: &day03::Solution,
+    },
+    Day {
+        number: 4,
+        title: "Repose Record",
+        solver: &day04::Solution,
+    },
+    Day {
+        number: 5,
+        title: "Alchemical Reduction",
+        solver: &day05::Solution,
+    },
+    Day {
+        number: 6,
+        title: "Chronal Coordinates",
+        solver: &day06::Solution,
+    },
+    Day {
+        number: 7,
+        title: "The Sum of Its Parts",
+        solver: &day07::Solution,
+    },
+    Day {
+        number: 8,
+        title: "Memory Maneuver",
+        solver: &day08::Solution,
+    },
+    Day {
+        number: 9,
+        title: "Marble Mania",
+        solver: &day09::Solution,
+    },
+    Day {
+        number: 10,
+        title: "The Stars Align",
+        solver: &day10::Solution,
+    },
+    Day {
+        number: 11,
+        title: "Chronal Charge",
+        solver: &day11::Solution,
+    },
+    Day {
+        number: 12,
+        title: "Subterranean Sustainability",
+        solver: &day12::Solution,
+    },
+    Day {
+        number: 13,
+        title: "Mine Cart Madness",
+        solver: &day13::Solution,
+    },
+    Day {
+        number: 14,
+        title: "Chocolate Charts",
+        solver: &day14::Solution,
+    },
+    Day {
+        number: 15,
+        title: "Beverage Bandits",
+        solver: &day15::Solution,
+    },
+    Day {
+        number: 16,
+        title: "Chronal Classification",
+        solver: &day16::Solution,
+    },
+    Day {
+        number: 17,
+        title: "Reservoir Research",
+        solver: &day17::Solution,
+    },
+    Day {
+        number: 18,
+        title: "Settlers of The North Pole",
+        solver: &day18::Solution,
+    },
+    Day {
+        number: 19,
+        title: "Go With The Flow",
+        solver: &day19::Solution,
+    },
+    Day {
+        number: 20,
+        title: "A Regular Map",
+        solver: &day20::Solution,
+    },
+];
+
+pub fn day(number: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|day| day.number == number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer::Answer;
+    use crate::solver::Part;
+
+    #[test]
+    fn test_days_are_ordered_and_unique() {
+        assert!(DAYS.windows(2).all(|w| w[0].number < w[1].number));
+    }
+
+    #[test]
+    fn test_day() {
+        assert_eq!(day(7).unwrap().title, "The Sum of Its Parts");
+        assert_eq!(day(1).unwrap().title, "Chronal Calibration");
+        assert!(day(0).is_none());
+    }
+
+    #[test]
+    fn test_solve() {
+        let input = include_str!("day08/input.txt");
+        let solver = day(8).unwrap().solver;
+        assert_eq!(
+            solver.solve(Part::One, input).unwrap(),
+            Answer::Integer(45194)
+        );
+        assert_eq!(
+            solver.solve(Part::Two, input).unwrap(),
+            Answer::Integer(22989)
+        );
+    }
+}
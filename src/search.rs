@@ -0,0 +1,334 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::ops::Add;
+
+use num::Zero;
+
+/// The outcome of a search: the distance to the nodes it reached and a
+/// shortest path to each.
+///
+/// Ties are broken by the nodes' `Ord`: among equally close goals the
+/// least is chosen, and each node's path runs through the least of the
+/// nodes that precede it on a shortest path.
+#[derive(Debug, Clone)]
+pub struct Search<N, C> {
+    distances: HashMap<N, C>,
+    parents: HashMap<N, N>,
+    goal: Option<N>,
+}
+
+impl<N: Hash + Eq + Clone, C: Copy> Search<N, C> {
+    /// Returns the goal the search stopped at, or None if it ran out of
+    /// nodes first.
+    pub fn goal(&self) -> Option<&N> {
+        self.goal.as_ref()
+    }
+
+    /// Returns the distance from the nearest start to `node`, or None if the
+    /// search did not reach it.
+    pub fn distance(&self, node: &N) -> Option<C> {
+        self.distances.get(node).copied()
+    }
+
+    /// Returns the distances of every node reached.  A search that stopped
+    /// at a goal only includes nodes that are no farther than the goal.
+    pub fn distances(&self) -> &HashMap<N, C> {
+        &self.distances
+    }
+
+    pub fn into_distances(self) -> HashMap<N, C> {
+        self.distances
+    }
+
+    /// Returns a shortest path from a start to `node`, including both, or
+    /// None if the search did not reach `node`.
+    pub fn path(&self, node: &N) -> Option<Vec<N>> {
+        self.distances.get(node)?;
+        let mut path = vec![node.clone()];
+        while let Some(parent) = self.parents.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Searches breadth first from all of `starts` at once, where `neighbors`
+/// returns the nodes one step from a node.  Stops at the least of the
+/// nearest nodes for which `is_goal` returns true; pass `|_| false` to get
+/// the distance to everything reachable.
+pub fn bfs<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, usize>
+where
+    N: Ord + Hash + Clone,
+    I: IntoIterator<Item = N>,
+{
+    let mut distances = HashMap::new();
+    let mut parents = HashMap::new();
+
+    let mut layer: Vec<N> = starts.into_iter().collect();
+    layer.sort();
+    layer.dedup();
+    for node in layer.iter() {
+        distances.insert(node.clone(), 0);
+    }
+
+    let mut distance = 0;
+    while !layer.is_empty() {
+        // The layer is sorted, so this finds the least goal.
+        if let Some(goal) = layer.iter().find(|node| is_goal(node)) {
+            let goal = Some(goal.clone());
+            return Search {
+                distances,
+                parents,
+                goal,
+            };
+        }
+        distance += 1;
+        // Expanding the layer in order makes the first node to reach a
+        // neighbor its least parent.
+        let mut next = Vec::new();
+        for node in layer.iter() {
+            for neighbor in neighbors(node) {
+                if !distances.contains_key(&neighbor) {
+                    distances.insert(neighbor.clone(), distance);
+                    parents.insert(neighbor.clone(), node.clone());
+                    next.push(neighbor);
+                }
+            }
+        }
+        next.sort();
+        layer = next;
+    }
+
+    Search {
+        distances,
+        parents,
+        goal: None,
+    }
+}
+
+/// Searches from all of `starts` at once in order of distance, where
+/// `neighbors` returns the nodes one step from a node along with the
+/// non-negative cost of the step.  Stops at the least of the nearest nodes
+/// for which `is_goal` returns true; pass `|_| false` to get the distance to
+/// everything reachable.
+pub fn dijkstra<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbors: impl FnMut(&N) -> I,
+    is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Ord + Hash + Clone,
+    C: Ord + Copy + Add<Output = C> + Zero,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(starts, neighbors, |_| C::zero(), is_goal)
+}
+
+/// Like [`dijkstra`], but visits nodes in order of their distance plus
+/// `heuristic`, an estimate of the remaining distance to a goal.  The
+/// distances are shortest if the heuristic never overestimates and never
+/// drops by more than the cost of a step.
+pub fn astar<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Ord + Hash + Clone,
+    C: Ord + Copy + Add<Output = C> + Zero,
+    I: IntoIterator<Item = (N, C)>,
+{
+    // The best known distance to each node not yet visited.
+    let mut best: HashMap<N, C> = HashMap::new();
+    let mut parents: HashMap<N, N> = HashMap::new();
+    let mut distances = HashMap::new();
+    let mut heap = BinaryHeap::new();
+    for start in starts {
+        if best.insert(start.clone(), C::zero()).is_none() {
+            heap.push(Reverse((heuristic(&start), start)));
+        }
+    }
+
+    let mut goal = None;
+    while let Some(Reverse((_, node))) = heap.pop() {
+        if distances.contains_key(&node) {
+            // A stale entry for a node already visited by a shorter path.
+            continue;
+        }
+        let distance = best[&node];
+        distances.insert(node.clone(), distance);
+        if is_goal(&node) {
+            goal = Some(node);
+            break;
+        }
+        for (neighbor, cost) in neighbors(&node) {
+            if distances.contains_key(&neighbor) {
+                continue;
+            }
+            let next = distance + cost;
+            match best.get(&neighbor) {
+                Some(&old) if next > old => {}
+                Some(&old) if next == old => {
+                    if parents.get(&neighbor).is_some_and(|parent| node < *parent) {
+                        parents.insert(neighbor, node.clone());
+                    }
+                }
+                _ => {
+                    best.insert(neighbor.clone(), next);
+                    parents.insert(neighbor.clone(), node.clone());
+                    heap.push(Reverse((next + heuristic(&neighbor), neighbor)));
+                }
+            }
+        }
+    }
+
+    Search {
+        distances,
+        parents,
+        goal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::{Grid, Point2D};
+
+    type Pos = Point2D<usize>;
+
+    const MAZE: &str = "\
+#######
+#.....#
+#.#.#.#
+#.....#
+#######
+";
+
+    fn open_neighbors(maze: &Grid<bool>) -> impl Fn(&Pos) -> Vec<Pos> + '_ {
+        |pos| {
+            pos.cardinal_neighbors_within(maze.width(), maze.height())
+                .filter(|p| maze[*p])
+                .collect()
+        }
+    }
+
+    fn parse_maze() -> Grid<bool> {
+        Grid::parse(MAZE, |ch| match ch {
+            '#' => Some(false),
+            '.' => Some(true),
+            _ => None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bfs() {
+        let maze = parse_maze();
+        let search = bfs([Pos::new(1, 1)], open_neighbors(&maze), |p| {
+            *p == Pos::new(5, 3)
+        });
+        assert_eq!(search.goal(), Some(&Pos::new(5, 3)));
+        assert_eq!(search.distance(&Pos::new(5, 3)), Some(6));
+        // Of the shortest paths, the one through the least (topmost) nodes.
+        assert_eq!(
+            search.path(&Pos::new(5, 3)).unwrap(),
+            vec![
+                Pos::new(1, 1),
+                Pos::new(2, 1),
+                Pos::new(3, 1),
+                Pos::new(4, 1),
+                Pos::new(5, 1),
+                Pos::new(5, 2),
+                Pos::new(5, 3),
+            ]
+        );
+        assert_eq!(search.path(&Pos::new(0, 0)), None);
+    }
+
+    #[test]
+    fn test_bfs_multiple_starts() {
+        let maze = parse_maze();
+        let starts = [Pos::new(5, 3), Pos::new(1, 1)];
+        let search = bfs(starts, open_neighbors(&maze), |_| false);
+        assert_eq!(search.goal(), None);
+        assert_eq!(search.distances().len(), 13);
+        assert_eq!(search.distance(&Pos::new(3, 2)), Some(3));
+        assert_eq!(search.path(&Pos::new(1, 1)).unwrap().len(), 1);
+
+        // Both (5, 1) and (1, 3) are two steps away; (5, 1) comes first in
+        // reading order.
+        let goals = [Pos::new(1, 3), Pos::new(5, 1)];
+        let search = bfs([Pos::new(3, 1)], open_neighbors(&maze), |p| {
+            goals.contains(p)
+        });
+        assert_eq!(search.goal(), Some(&Pos::new(5, 1)));
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        // A chain a - b - c - d with a costly shortcut a - d.
+        let edges = |n: &char| -> Vec<(char, u32)> {
+            match n {
+                'a' => vec![('b', 1), ('d', 5)],
+                'b' => vec![('a', 1), ('c', 1)],
+                'c' => vec![('b', 1), ('d', 1)],
+                'd' => vec![('a', 5), ('c', 1)],
+                _ => vec![],
+            }
+        };
+        let search = dijkstra(['a'], edges, |n| *n == 'd');
+        assert_eq!(search.goal(), Some(&'d'));
+        assert_eq!(search.distance(&'d'), Some(3));
+        assert_eq!(search.path(&'d').unwrap(), vec!['a', 'b', 'c', 'd']);
+
+        let everything = dijkstra(['d'], edges, |_| false);
+        assert_eq!(everything.distances().len(), 4);
+        assert_eq!(everything.distance(&'a'), Some(3));
+    }
+
+    #[test]
+    fn test_dijkstra_state() {
+        // Reach 10 from 1 by adding one (cost 1) or doubling (cost 2), with
+        // at most one doubling: the state is (value, doubled).
+        let steps = |&(n, doubled): &(u32, bool)| {
+            let mut next = vec![((n + 1, doubled), 1)];
+            if !doubled {
+                next.push(((n * 2, true), 2));
+            }
+            next
+        };
+        let search = dijkstra([(1, false)], steps, |&(n, _)| n == 10);
+        assert_eq!(search.goal(), Some(&(10, true)));
+        assert_eq!(search.distance(&(10, true)), Some(6));
+    }
+
+    #[test]
+    fn test_astar() {
+        let maze = parse_maze();
+        let goal = Pos::new(5, 3);
+        let neighbors = |p: &Pos| {
+            open_neighbors(&maze)(p)
+                .into_iter()
+                .map(|n| (n, 1_usize))
+                .collect::<Vec<_>>()
+        };
+        let search = astar(
+            [Pos::new(1, 1)],
+            neighbors,
+            |p| p.manhattan_distance(goal),
+            |p| *p == goal,
+        );
+        assert_eq!(search.distance(&goal), Some(6));
+        let path = search.path(&goal).unwrap();
+        assert_eq!(path.len(), 7);
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.windows(2).all(|w| w[0].manhattan_distance(w[1]) == 1));
+    }
+}
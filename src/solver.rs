@@ -0,0 +1,136 @@
+use std::any::Any;
+use std::fmt;
+
+use crate::answer::Answer;
+use crate::parse::ParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Part {
+    One,
+    Two,
+}
+
+pub const PARTS: [Part; 2] = [Part::One, Part::Two];
+
+impl Part {
+    /// Returns the part for the puzzle's numbering, 1 or 2.
+    pub fn from_number(number: u8) -> Option<Part> {
+        match number {
+            1 => Some(Part::One),
+            2 => Some(Part::Two),
+            _ => None,
+        }
+    }
+
+    pub fn number(&self) -> u8 {
+        match *self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+/// A well-formed input that one part cannot be solved for, such as day 13
+/// input with too few carts to leave a last one standing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveError {
+    pub message: String,
+}
+
+impl SolveError {
+    pub fn new(message: impl Into<String>) -> SolveError {
+        SolveError {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// Why [`DynSolver::solve`] produced no answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Parse(ParseError),
+    Solve(SolveError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "invalid input: {}", e),
+            Error::Solve(e) => write!(f, "no answer: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Error {
+        Error::Parse(e)
+    }
+}
+
+impl From<SolveError> for Error {
+    fn from(e: SolveError) -> Error {
+        Error::Solve(e)
+    }
+}
+
+/// A day's solution, split into parsing the puzzle input and solving each
+/// part from the parsed form.  Checks that only one part needs belong in
+/// that part, so the other can still be solved.
+pub trait Solver {
+    type Parsed: 'static;
+
+    fn parse(&self, input: &str) -> Result<Self::Parsed, ParseError>;
+    fn part_one(&self, parsed: &Self::Parsed) -> Result<Answer, SolveError>;
+    fn part_two(&self, parsed: &Self::Parsed) -> Result<Answer, SolveError>;
+}
+
+/// An object safe form of [`Solver`], so days with different parsed types
+/// can live in one registry.  Implemented for every [`Solver`].
+pub trait DynSolver: Sync {
+    fn parse_input(&self, input: &str) -> Result<Box<dyn Any>, ParseError>;
+
+    /// Solves `part` from the value returned by
+    /// [`parse_input`](DynSolver::parse_input).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parsed` did not come from this solver.
+    fn solve_part(&self, part: Part, parsed: &dyn Any) -> Result<Answer, SolveError>;
+
+    fn solve(&self, part: Part, input: &str) -> Result<Answer, Error> {
+        let parsed = self.parse_input(input)?;
+        Ok(self.solve_part(part, parsed.as_ref())?)
+    }
+}
+
+impl<S: Solver + Sync> DynSolver for S {
+    fn parse_input(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+        Ok(Box::new(self.parse(input)?))
+    }
+
+    fn solve_part(&self, part: Part, parsed: &dyn Any) -> Result<Answer, SolveError> {
+        let parsed = parsed
+            .downcast_ref::<S::Parsed>()
+            .expect("bug: parsed input from a different solver");
+        match part {
+            Part::One => self.part_one(parsed),
+            Part::Two => self.part_two(parsed),
+        }
+    }
+}
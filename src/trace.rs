@@ -0,0 +1,133 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+/// How much detail an event carries.  Later levels are more verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Level {
+    /// A few lines per solve, e.g. a search converging.
+    Info,
+    /// Intermediate values.
+    Debug,
+    /// Every step, including whole map dumps.
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        };
+        f.pad(name)
+    }
+}
+
+/// Receives the events solvers emit.
+pub trait Observer {
+    fn enabled(&self, level: Level) -> bool;
+    fn event(&self, level: Level, message: fmt::Arguments);
+}
+
+/// Writes events up to `max_level` to stderr.
+pub struct Stderr {
+    pub max_level: Level,
+}
+
+impl Observer for Stderr {
+    fn enabled(&self, level: Level) -> bool {
+        level <= self.max_level
+    }
+
+    fn event(&self, level: Level, message: fmt::Arguments) {
+        eprintln!("[{}] {}", level, message);
+    }
+}
+
+thread_local! {
+    static OBSERVER: RefCell<Option<Rc<dyn Observer>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `observer` receiving the events emitted on this thread,
+/// restoring the previous observer afterwards.  Without an observer events
+/// are discarded.
+pub fn with_observer<R>(observer: Rc<dyn Observer>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Rc<dyn Observer>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            OBSERVER.with(|o| *o.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(OBSERVER.with(|o| o.borrow_mut().replace(observer)));
+    f()
+}
+
+/// Returns whether an event at `level` would be observed.  Use it to skip
+/// expensive work such as rendering a map.
+pub fn enabled(level: Level) -> bool {
+    OBSERVER.with(|o| o.borrow().as_ref().is_some_and(|o| o.enabled(level)))
+}
+
+pub fn emit(level: Level, message: fmt::Arguments) {
+    let observer = OBSERVER.with(|o| o.borrow().clone());
+    if let Some(observer) = observer
+        && observer.enabled(level)
+    {
+        observer.event(level, message);
+    }
+}
+
+/// Emits an event, formatting the message only if it will be observed.
+macro_rules! event {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::trace::enabled($level) {
+            $crate::trace::emit($level, format_args!($($arg)+));
+        }
+    };
+}
+
+pub(crate) use event;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Collect {
+        max_level: Level,
+        events: RefCell<Vec<(Level, String)>>,
+    }
+
+    impl Observer for Collect {
+        fn enabled(&self, level: Level) -> bool {
+            level <= self.max_level
+        }
+
+        fn event(&self, level: Level, message: fmt::Arguments) {
+            self.events.borrow_mut().push((level, message.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_with_observer() {
+        let collect = Rc::new(Collect {
+            max_level: Level::Debug,
+            events: RefCell::new(Vec::new()),
+        });
+        event!(Level::Info, "not observed");
+        with_observer(collect.clone(), || {
+            assert!(enabled(Level::Debug));
+            assert!(!enabled(Level::Trace));
+            event!(Level::Info, "answer {}", 42);
+            event!(Level::Trace, "too verbose");
+        });
+        assert!(!enabled(Level::Info));
+        assert_eq!(
+            *collect.events.borrow(),
+            vec![(Level::Info, "answer 42".to_string())]
+        );
+    }
+}
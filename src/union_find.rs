@@ -0,0 +1,165 @@
+/// A disjoint-set forest over the elements `0..len`, with path compression
+/// and union by rank.
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+    count: usize,
+}
+
+impl UnionFind {
+    /// Creates `len` singleton sets.
+    pub fn new(len: usize) -> UnionFind {
+        UnionFind {
+            parents: (0..len).collect(),
+            ranks: vec![0; len],
+            count: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Returns the number of disjoint sets.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the representative of the set containing `x`.  Panics if `x`
+    /// is out of range.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        // Point everything on the way directly at the root.
+        let mut x = x;
+        while self.parents[x] != root {
+            x = std::mem::replace(&mut self.parents[x], root);
+        }
+        root
+    }
+
+    /// Merges the sets containing `a` and `b`, returning false if they were
+    /// already the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        // Hang the shallower tree under the deeper one.
+        let (child, root) = if self.ranks[a] < self.ranks[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[child] = root;
+        if self.ranks[child] == self.ranks[root] {
+            self.ranks[root] += 1;
+        }
+        self.count -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+/// The connected components of a set of items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clusters {
+    pub count: usize,
+    /// The component of each item, numbered from 0 in order of each
+    /// component's first item.
+    pub membership: Vec<usize>,
+}
+
+impl Clusters {
+    /// Returns the indices of the items in each component.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut components = vec![Vec::new(); self.count];
+        for (item, &component) in self.membership.iter().enumerate() {
+            components[component].push(item);
+        }
+        components
+    }
+}
+
+/// Clusters `points` into components, joining every pair for which `close`
+/// returns true, and components that share a point.  Compares every pair,
+/// so takes quadratic time.
+pub fn cluster<P>(points: &[P], mut close: impl FnMut(&P, &P) -> bool) -> Clusters {
+    let mut sets = UnionFind::new(points.len());
+    for (i, a) in points.iter().enumerate() {
+        for (j, b) in points.iter().enumerate().skip(i + 1) {
+            if !sets.connected(i, j) && close(a, b) {
+                sets.union(i, j);
+            }
+        }
+    }
+
+    let mut numbers = vec![None; points.len()];
+    let mut count = 0;
+    let membership = (0..points.len())
+        .map(|i| {
+            let root = sets.find(i);
+            *numbers[root].get_or_insert_with(|| {
+                count += 1;
+                count - 1
+            })
+        })
+        .collect();
+    Clusters { count, membership }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point4D;
+
+    #[test]
+    fn test_union_find() {
+        let mut sets = UnionFind::new(6);
+        assert_eq!(sets.count(), 6);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(!sets.union(0, 2));
+        assert_eq!(sets.count(), 3);
+        assert!(sets.connected(0, 3));
+        assert!(!sets.connected(0, 4));
+        assert_eq!(sets.find(2), sets.find(1));
+        assert_eq!(sets.len(), 6);
+    }
+
+    #[test]
+    fn test_cluster() {
+        // The first constellation example from day 25, 2018.
+        let points = [
+            Point4D::new(0, 0, 0, 0),
+            Point4D::new(3, 0, 0, 0),
+            Point4D::new(0, 3, 0, 0),
+            Point4D::new(0, 0, 3, 0),
+            Point4D::new(0, 0, 0, 3),
+            Point4D::new(0, 0, 0, 6),
+            Point4D::new(9, 0, 0, 0),
+            Point4D::new(12, 0, 0, 0),
+        ];
+        let clusters = cluster(&points, |a, b| a.manhattan_distance(*b) <= 3);
+        assert_eq!(clusters.count, 2);
+        assert_eq!(clusters.membership, vec![0, 0, 0, 0, 0, 0, 1, 1]);
+        assert_eq!(
+            clusters.components(),
+            vec![vec![0, 1, 2, 3, 4, 5], vec![6, 7]]
+        );
+
+        let empty: [i32; 0] = [];
+        assert_eq!(cluster(&empty, |_, _| true).count, 0);
+    }
+}
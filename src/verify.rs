@@ -0,0 +1,257 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::answers::Answers;
+use crate::registry;
+use crate::solver::{PARTS, Part};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    /// The solver returned this answer and there was none to compare with.
+    Unchecked(String),
+    Mismatch {
+        expected: String,
+        actual: String,
+    },
+    /// The input could not be loaded or parsed, or the solver panicked.
+    Fail(String),
+}
+
+impl Outcome {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Outcome::Mismatch { .. } | Outcome::Fail(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Verification {
+    pub input: String,
+    pub day: u8,
+    pub part: Part,
+    pub outcome: Outcome,
+    pub duration: Duration,
+}
+
+/// Runs the solver for every answer in `answers`, loading inputs by name
+/// and day with `load_input`, and compares what it returns.
+pub fn verify<E: ToString>(
+    answers: &Answers,
+    load_input: impl Fn(&str, u8) -> Result<String, E>,
+) -> Vec<Verification> {
+    answers
+        .iter()
+        .map(|(key, expected)| {
+            verification(&key.input, key.day, key.part, Some(expected), &load_input)
+        })
+        .collect()
+}
+
+/// Runs both parts of each of `days` on the input named `input`, comparing
+/// with `answers` where they have an answer.  The parts run on up to
+/// `threads` threads; the results are in day and part order regardless.
+pub fn run_all<E: ToString>(
+    days: &[u8],
+    input: &str,
+    answers: &Answers,
+    load_input: impl Fn(&str, u8) -> Result<String, E> + Sync,
+    threads: usize,
+) -> Vec<Verification> {
+    let jobs: Vec<(u8, Part)> = days
+        .iter()
+        .flat_map(|&day| PARTS.map(|part| (day, part)))
+        .collect();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(jobs.len()));
+    thread::scope(|s| {
+        for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+            s.spawn(|| {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(day, part)) = jobs.get(i) else {
+                        break;
+                    };
+                    let expected = answers.get(input, day, part);
+                    let v = verification(input, day, part, expected, &load_input);
+                    results.lock().unwrap().push((i, v));
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, v)| v).collect()
+}
+
+fn verification<E: ToString>(
+    input: &str,
+    day: u8,
+    part: Part,
+    expected: Option<&str>,
+    load_input: impl Fn(&str, u8) -> Result<String, E>,
+) -> Verification {
+    let start = Instant::now();
+    let outcome = check(day, part, expected, || {
+        load_input(input, day).map_err(|e| e.to_string())
+    });
+    Verification {
+        input: input.to_string(),
+        day,
+        part,
+        outcome,
+        duration: start.elapsed(),
+    }
+}
+
+fn check(
+    day: u8,
+    part: Part,
+    expected: Option<&str>,
+    load_input: impl FnOnce() -> Result<String, String>,
+) -> Outcome {
+    let Some(day) = registry::day(day) else {
+        return Outcome::Fail(format!("no solver for day {}", day));
+    };
+    let input = match load_input() {
+        Ok(input) => input,
+        Err(e) => return Outcome::Fail(e),
+    };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| day.solver.solve(part, &input)));
+    match result {
+        Ok(Ok(answer)) => {
+            let actual = answer.to_string();
+            match expected {
+                None => Outcome::Unchecked(actual),
+                Some(expected) if actual == expected => Outcome::Pass,
+                Some(expected) => Outcome::Mismatch {
+                    expected: expected.to_string(),
+                    actual,
+                },
+            }
+        }
+        Ok(Err(e)) => Outcome::Fail(e.to_string()),
+        Err(payload) => Outcome::Fail(panic_message(payload.as_ref())),
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        format!("panicked: {}", s)
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        format!("panicked: {}", s)
+    } else {
+        "panicked".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inputs;
+
+    #[test]
+    fn test_verify() {
+        let answers = Answers::parse(
+            "\
+builtin 8 1 45194
+builtin 8 2 1
+builtin 9 1 367802
+missing 8 1 45194
+builtin 99 1 0
+",
+        )
+        .unwrap();
+        let load_input = |name: &str, day| {
+            if name == inputs::BUILTIN {
+                inputs::builtin(day).map(str::to_string).ok_or("no input")
+            } else {
+                Err("no input")
+            }
+        };
+        let outcomes: Vec<Outcome> = verify(&answers, load_input)
+            .into_iter()
+            .map(|v| v.outcome)
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                Outcome::Pass,
+                Outcome::Mismatch {
+                    expected: "1".to_string(),
+                    actual: "22989".to_string()
+                },
+                Outcome::Pass,
+                Outcome::Fail("no solver for day 99".to_string()),
+                Outcome::Fail("no input".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_solve_error() {
+        // Neither ID is one letter from the other, which only part two needs.
+        let load_input = || Ok("abc\nxyz\n".to_string());
+        assert_eq!(
+            check(2, Part::One, None, load_input),
+            Outcome::Unchecked("0".to_string())
+        );
+        assert_eq!(
+            check(2, Part::Two, None, load_input),
+            Outcome::Fail("no answer: expected two IDs that differ in one position".to_string())
+        );
+        assert_eq!(
+            check(2, Part::One, None, || Ok("aBc\n".to_string())),
+            Outcome::Fail(
+                "invalid input: line 1, column 2: expected a lowercase letter: \"B\"".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_run_all() {
+        let answers = Answers::parse(
+            "builtin 8 1 45194
+builtin 9 1 0
+",
+        )
+        .unwrap();
+        let store = inputs::InputStore::new("missing");
+        let load_input = |name: &str, day| store.load(name, day);
+        let results = run_all(&[8, 9, 99], inputs::BUILTIN, &answers, load_input, 4);
+        let summary: Vec<(u8, Part, Outcome)> = results
+            .into_iter()
+            .map(|v| (v.day, v.part, v.outcome))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (8, Part::One, Outcome::Pass),
+                (8, Part::Two, Outcome::Unchecked("22989".to_string())),
+                (
+                    9,
+                    Part::One,
+                    Outcome::Mismatch {
+                        expected: "0".to_string(),
+                        actual: "367802".to_string()
+                    }
+                ),
+                (9, Part::Two, Outcome::Unchecked("2996043280".to_string())),
+                (
+                    99,
+                    Part::One,
+                    Outcome::Fail("no solver for day 99".to_string())
+                ),
+                (
+                    99,
+                    Part::Two,
+                    Outcome::Fail("no solver for day 99".to_string())
+                ),
+            ]
+        );
+        let failures = summary.iter().filter(|(_, _, o)| o.is_failure()).count();
+        assert_eq!(failures, 3);
+    }
+}